@@ -2,11 +2,8 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 //! This transformation pass applies proof abstractions to some structures that are hard to reason
-//! about. Today we only replace certain function calls and we only support behaviorally equivalent
-//! abstractions.
-//!
-//! In order to support over-approximations and under-approximations we would need to provide a
-//! better mechanism to report proof results to reflect that.
+//! about. We replace certain function calls with either one of RMC's built-in abstractions, or
+//! with a user-configured stub.
 //!
 //! The algorithm today is rather simple:
 //! 1- Create a map of abstraction types and the def_id for the abstraction implementation. We use
@@ -27,6 +24,7 @@ use rustc_span::Symbol;
 use rustc_data_structures::fx::FxHashMap;
 use rustc_middle::mir::MirPass;
 use rustc_middle::ty::print::with_no_trimmed_paths;
+use std::cell::RefCell;
 use std::fmt::{Debug, Formatter};
 use std::rc::Rc;
 use tracing::{debug, error, trace};
@@ -35,14 +33,18 @@ const RMC_STR: &'static str = "rmc";
 
 /// A trait that represents an MIR pass that applies function call abstractions.
 ///
-/// We may want to replace some function that is hard to reason about. Today we only support
-/// behaviorally equivalent abstractions.
-///
-/// In order to support over-approximations and under-approximations we would need to provide a
-/// better mechanism to report proof results to reflect that.
+/// We may want to replace some function that is hard to reason about, either with one of RMC's
+/// built-in behaviorally-equivalent abstractions, or with a user-configured stub. User stubs are
+/// additionally classified as [`Approximation::Over`] or [`Approximation::Under`], so a proof
+/// that only holds because of an under-approximating stub can be reported as unsound rather than
+/// fully verified.
 pub struct FnCallAbstractionPass {
     abstraction_ids: FxHashMap<AbstractionsEnum, DefId>,
     abstractions: Vec<Rc<dyn FnAbstraction>>,
+    /// Stubs applied while running this pass, recorded so the driver can surface them as part of
+    /// the harness's metadata (e.g. to flag a proof as unsound when it only holds under an
+    /// under-approximating stub).
+    applied_stubs: Rc<RefCell<Vec<AppliedStub>>>,
 }
 
 impl<'tcx> MirPass<'tcx> for FnCallAbstractionPass {
@@ -67,16 +69,31 @@ impl<'tcx> MirPass<'tcx> for FnCallAbstractionPass {
 
 impl FnCallAbstractionPass {
     pub fn new(tcx: TyCtxt<'tcx>) -> FnCallAbstractionPass {
+        Self::with_stubs(tcx, vec![])
+    }
+
+    /// Build the pass with RMC's built-in abstractions plus `stubs`, a set of user-configured
+    /// `(original_fn, replacement_fn)` mappings declared on the harness currently being compiled
+    /// (e.g. via a `#[kani::stub(original, replacement)]`-style annotation).
+    pub fn with_stubs(tcx: TyCtxt<'tcx>, stubs: Vec<StubSpec>) -> FnCallAbstractionPass {
         let abstraction_ids = get_rmc_definitions(tcx);
-        Self {
-            abstraction_ids: abstraction_ids.clone(),
-            abstractions: vec![
-                ptr_read(&abstraction_ids),
-                ptr_write(&abstraction_ids),
-                mem_swap(&abstraction_ids),
-                mem_replace(&abstraction_ids),
-            ],
-        }
+        let applied_stubs = Rc::new(RefCell::new(vec![]));
+        let mut abstractions: Vec<Rc<dyn FnAbstraction>> = vec![
+            ptr_read(&abstraction_ids),
+            ptr_write(&abstraction_ids),
+            mem_swap(&abstraction_ids),
+            mem_replace(&abstraction_ids),
+            memchr(&abstraction_ids),
+        ];
+        abstractions
+            .extend(stubs.into_iter().map(|spec| user_stub(tcx, spec, applied_stubs.clone())));
+        Self { abstraction_ids, abstractions, applied_stubs }
+    }
+
+    /// Stubs that were actually applied while running this pass, along with whether each one is
+    /// behaviorally exact or an over-/under-approximation of the function it replaced.
+    pub fn applied_stubs(&self) -> Vec<AppliedStub> {
+        self.applied_stubs.borrow().clone()
     }
 
     fn process_bb(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>, bb: BasicBlock) -> bool {
@@ -145,34 +162,131 @@ impl FnAbstraction for FnReplacement {
         body: &mut Body<'tcx>,
         terminator: Terminator<'tcx>,
     ) -> Result<Terminator<'tcx>, String> {
-        if let TerminatorKind::Call {
-            ref func,
-            args,
-            destination,
-            cleanup,
-            from_hir_call,
-            fn_span,
-        } = terminator.kind
-        {
-            if let ty::FnDef(_, subst) = func.ty(body, tcx).kind() {
-                let fn_handle = Operand::function_handle(tcx, self.abs_id, subst, fn_span);
-                let new_terminator = Terminator {
-                    source_info: terminator.source_info,
-                    kind: TerminatorKind::Call {
-                        func: fn_handle,
-                        args,
-                        destination,
-                        cleanup,
-                        from_hir_call,
-                        fn_span,
-                    },
-                };
-                debug!(?func, "Replaced call");
-                return Ok(new_terminator);
-            }
+        replace_call_target(tcx, self.abs_id, body, terminator)
+            .ok_or_else(|| format!("Failed to replace function. Target abstraction: {:?}", self))
+    }
+}
+
+/// Rewrite a `Call` terminator so it invokes `replacement_id` instead of whatever it originally
+/// called, keeping the arguments, destination and generic substitutions untouched. Shared by
+/// `FnReplacement` (RMC's built-in abstractions) and `UserStub` (user-configured stubs).
+fn replace_call_target(
+    tcx: TyCtxt<'tcx>,
+    replacement_id: DefId,
+    body: &Body<'tcx>,
+    terminator: Terminator<'tcx>,
+) -> Option<Terminator<'tcx>> {
+    if let TerminatorKind::Call { ref func, args, destination, cleanup, from_hir_call, fn_span } =
+        terminator.kind
+    {
+        if let ty::FnDef(_, subst) = func.ty(body, tcx).kind() {
+            let fn_handle = Operand::function_handle(tcx, replacement_id, subst, fn_span);
+            let new_terminator = Terminator {
+                source_info: terminator.source_info,
+                kind: TerminatorKind::Call {
+                    func: fn_handle,
+                    args,
+                    destination,
+                    cleanup,
+                    from_hir_call,
+                    fn_span,
+                },
+            };
+            debug!(?func, "Replaced call");
+            return Some(new_terminator);
         }
-        Err(format!("Failed to replace function. Target abstraction: {:?}", self))
     }
+    None
+}
+
+/// How a user-configured stub's behavior relates to the function it replaces.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum Approximation {
+    /// The stub behaves exactly like the original function. A proof under this stub is as sound
+    /// as one that calls the original function directly.
+    Exact,
+    /// The stub exhibits a superset of the original function's behaviors. A *passing* proof still
+    /// holds for the original function, but a *failing* one may be a spurious counterexample the
+    /// original function can't produce.
+    Over,
+    /// The stub only exhibits a subset of the original function's behaviors. A proof that passes
+    /// under this stub is unsound: it hasn't covered every behavior the original function could
+    /// have exhibited, and must be reported as such rather than as fully "verified".
+    Under,
+}
+
+/// A `(original_fn, replacement_fn)` stub the user configured on the harness being compiled, with
+/// both already resolved to a `DefId` by the caller.
+#[derive(Clone, Debug)]
+pub struct StubSpec {
+    pub original_id: DefId,
+    pub replacement_id: DefId,
+    pub approximation: Approximation,
+}
+
+/// Record of a stub that was actually applied while running the pass, for surfacing in harness
+/// metadata alongside the verification result.
+#[derive(Clone, Debug)]
+pub struct AppliedStub {
+    pub original_fn: String,
+    pub replacement_fn: String,
+    pub approximation: Approximation,
+}
+
+/// A single user-configured `(original_fn, replacement_fn)` stub, applied the same way as the
+/// built-in `FnReplacement` abstractions but sourced from the harness rather than hardcoded here.
+#[derive(Debug)]
+struct UserStub {
+    spec: StubSpec,
+    applied: Rc<RefCell<Vec<AppliedStub>>>,
+}
+
+impl FnAbstraction for UserStub {
+    fn name(&self) -> &'static str {
+        "UserStub"
+    }
+
+    fn matches(&self, tcx: TyCtxt<'tcx>, body: &Body<'tcx>, func: &Operand<'tcx>) -> bool {
+        if let ty::FnDef(ref def_id, _) = func.ty(body, tcx).kind() {
+            return *def_id == self.spec.original_id;
+        }
+        false
+    }
+
+    fn handle(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        _: &FxHashMap<AbstractionsEnum, DefId>,
+        body: &mut Body<'tcx>,
+        terminator: Terminator<'tcx>,
+    ) -> Result<Terminator<'tcx>, String> {
+        let new_terminator = replace_call_target(tcx, self.spec.replacement_id, body, terminator)
+            .ok_or_else(|| format!("Failed to apply stub: {:?}", self.spec))?;
+        self.applied.borrow_mut().push(AppliedStub {
+            original_fn: with_no_trimmed_paths(|| tcx.def_path_str(self.spec.original_id)),
+            replacement_fn: with_no_trimmed_paths(|| tcx.def_path_str(self.spec.replacement_id)),
+            approximation: self.spec.approximation,
+        });
+        Ok(new_terminator)
+    }
+}
+
+#[inline(always)]
+fn user_stub(
+    tcx: TyCtxt<'tcx>,
+    spec: StubSpec,
+    applied: Rc<RefCell<Vec<AppliedStub>>>,
+) -> Rc<dyn FnAbstraction> {
+    // The replacement must type-check against the original: same signature, modulo the generic
+    // substitutions applied at the call site (those are preserved as-is by `replace_call_target`).
+    if tcx.fn_sig(spec.original_id) != tcx.fn_sig(spec.replacement_id) {
+        bug!(
+            "Stub replacement does not type-check against the original function: {:?} vs {:?}",
+            spec.original_id,
+            spec.replacement_id
+        );
+    }
+    Rc::new(UserStub { spec, applied })
 }
 
 #[inline(always)]
@@ -214,6 +328,15 @@ fn mem_replace(abstraction_ids: &FxHashMap<AbstractionsEnum, DefId>) -> Rc<dyn F
     })
 }
 
+#[inline(always)]
+fn memchr(abstraction_ids: &FxHashMap<AbstractionsEnum, DefId>) -> Rc<dyn FnAbstraction> {
+    Rc::new(FnReplacement {
+        original_fns: vec!["core::slice::memchr::memchr"],
+        abs_id: *abstraction_ids.get(&AbstractionsEnum::Memchr).unwrap(),
+        name: "Memchr",
+    })
+}
+
 #[inline(always)]
 fn ptr_write(abstraction_ids: &FxHashMap<AbstractionsEnum, DefId>) -> Rc<dyn FnAbstraction> {
     Rc::new(FnReplacement {
@@ -238,6 +361,7 @@ pub enum AbstractionsEnum {
     PtrWrite,
     MemSwap,
     MemReplace,
+    Memchr,
 }
 
 impl AbstractionsEnum {
@@ -250,6 +374,7 @@ impl AbstractionsEnum {
             AbstractionsEnum::PtrWrite => Symbol::intern("RmcPtrWrite"),
             AbstractionsEnum::MemSwap => Symbol::intern("RmcMemSwap"),
             AbstractionsEnum::MemReplace => Symbol::intern("RmcMemReplace"),
+            AbstractionsEnum::Memchr => Symbol::intern("RmcMemchr"),
         }
     }
 }
@@ -268,6 +393,7 @@ fn get_rmc_definitions(tcx: TyCtxt<'tcx>) -> FxHashMap<AbstractionsEnum, DefId>
             AbstractionsEnum::PtrWrite,
             AbstractionsEnum::MemSwap,
             AbstractionsEnum::MemReplace,
+            AbstractionsEnum::Memchr,
         ];
         for abs in abstractions {
             if let Some(item) = diagnostics.name_to_id.get(&abs.attribute()) {
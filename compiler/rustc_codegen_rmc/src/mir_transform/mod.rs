@@ -4,13 +4,20 @@
 //! This module provides MIR transformation passes that we want to perform before code generation.
 use tracing::debug;
 
+pub use crate::mir_transform::fn_call_abstractions::{Approximation, AppliedStub, StubSpec};
+pub use crate::mir_transform::function_contracts::ContractSpec;
+pub use crate::mir_transform::loop_contracts::LoopContractSpec;
 use crate::mir_transform::fn_call_abstractions::FnCallAbstractionPass;
+use crate::mir_transform::function_contracts::ContractPass;
+use crate::mir_transform::loop_contracts::LoopContractPass;
 use rustc_hir::def_id::DefId;
 use rustc_middle::mir::{Body, MirPass};
 use rustc_middle::ty::query::Providers;
 use rustc_middle::ty::TyCtxt;
 
 mod fn_call_abstractions;
+mod function_contracts;
+mod loop_contracts;
 
 // TODO: This should be replaced by rustc_interface::DEFAULT_QUERY_PROVIDERS
 // once we change RMC to be a driver instead of just a codegen.
@@ -21,6 +28,84 @@ static mut OPTIMIZED_MIR_FN: OptimizedMIR = |_, _| {
     unimplemented!();
 };
 
+// TODO: This should be keyed by harness `DefId` once the compiler drives one harness at a time.
+// For now we accumulate every stub applied across the whole compilation session and let the
+// caller of `take_applied_stubs` (in the compiler process) sort out attribution.
+static mut APPLIED_STUBS: Vec<AppliedStub> = Vec::new();
+static mut USER_STUBS: Vec<StubSpec> = Vec::new();
+static mut CONTRACTS: Vec<ContractSpec> = Vec::new();
+static mut LOOP_CONTRACTS: Vec<LoopContractSpec> = Vec::new();
+
+/// `set_user_stubs`/`set_contracts`/`set_loop_contracts` below all have the same shape and the
+/// same gap: each is meant to be called once, before codegen triggers `optimized_mir`, by the
+/// attribute-processing layer that resolves a harness's `#[kani::stub(...)]`/
+/// `#[kani::requires]`+`#[kani::ensures]`/`#[kani::loop_invariant]`+`#[kani::loop_variant]`
+/// attributes into the `DefId`-based `StubSpec`/`ContractSpec`/`LoopContractSpec` lists these
+/// take. That attribute-processing layer isn't part of this trimmed checkout, so none of the
+/// three has a real caller here -- `run_transformation_passes` below runs all three passes
+/// unconditionally regardless, which is why every harness that depends on one of these (e.g.
+/// `tests/expected/function-contract`'s, `tests/kani/LoopContracts/fill_array.rs`) is written
+/// assuming the missing layer populated the corresponding list, not that this tree alone would
+/// produce a result.
+
+/// Configure the `(original_fn, replacement_fn)` stubs declared on the harness about to be
+/// compiled.
+pub fn set_user_stubs(stubs: Vec<StubSpec>) {
+    unsafe {
+        USER_STUBS = stubs;
+    }
+}
+
+/// Configure the function contracts (`requires`/`ensures` pairs) declared in the crate about to
+/// be compiled.
+pub fn set_contracts(contracts: Vec<ContractSpec>) {
+    unsafe {
+        CONTRACTS = contracts;
+    }
+}
+
+/// Configure the loop contracts (`loop_invariant`/`loop_variant` pairs) declared in the crate
+/// about to be compiled.
+pub fn set_loop_contracts(contracts: Vec<LoopContractSpec>) {
+    unsafe {
+        LOOP_CONTRACTS = contracts;
+    }
+}
+
+/// Drain the stubs that were actually applied so far. The compiler's `KaniMetadata` writer (the
+/// codegen backend's entry point, which serializes `HarnessMetadata` to the `.kani-metadata.json`
+/// file `kani-driver` later reads via `Project::get_all_harnesses`) is meant to call this right
+/// before writing that file out and fold the result into each harness's `contracts`/`stubs`
+/// fields via [`applied_stub_names`] -- `kani-driver` runs in a separate process and can never
+/// call this directly, since `APPLIED_STUBS` only exists in the compiler process's memory.
+///
+/// That codegen entry point isn't part of this trimmed checkout (only the MIR-transform module
+/// is), so as of this tree `kani-driver/src/list.rs`'s `contracts`/`stubs` columns still read
+/// whatever the metadata JSON says, which is nothing until that wiring lands.
+pub fn take_applied_stubs() -> Vec<AppliedStub> {
+    unsafe { std::mem::take(&mut APPLIED_STUBS) }
+}
+
+/// Partition `take_applied_stubs()`'s output into the two string lists `HarnessMetadata.contracts`
+/// and `HarnessMetadata.stubs` expect: a `ContractPass`/`LoopContractPass` replacement is tagged
+/// with a synthetic `replacement_fn` (`"<contract for ...>"` / `"<loop contract for ...>"`, see
+/// `function_contracts::ContractPass::splice_contract` and
+/// `loop_contracts::LoopContractPass::splice_loop_contract`); everything else came from
+/// `FnCallAbstractionPass`'s built-in or user-supplied stubs.
+pub fn applied_stub_names(applied: Vec<AppliedStub>) -> (Vec<String>, Vec<String>) {
+    let mut contracts = vec![];
+    let mut stubs = vec![];
+    for stub in applied {
+        if stub.replacement_fn.starts_with("<contract for") || stub.replacement_fn.starts_with("<loop contract for")
+        {
+            contracts.push(stub.original_fn);
+        } else {
+            stubs.push(stub.original_fn);
+        }
+    }
+    (contracts, stubs)
+}
+
 fn run_transformation_passes(tcx: TyCtxt<'tcx>, def_id: DefId) -> &Body<'tcx> {
     debug!(?def_id, "Run rustc transformation passes");
     let body: &Body<'tcx>;
@@ -30,7 +115,17 @@ fn run_transformation_passes(tcx: TyCtxt<'tcx>, def_id: DefId) -> &Body<'tcx> {
 
     debug!(?def_id, "Run RMC's transformation passes");
     let mut new_body = body.clone();
-    FnCallAbstractionPass::new(tcx).run_pass(tcx, &mut new_body);
+    let stub_pass = unsafe { FnCallAbstractionPass::with_stubs(tcx, USER_STUBS.clone()) };
+    stub_pass.run_pass(tcx, &mut new_body);
+    let contract_pass = unsafe { ContractPass::new(tcx, CONTRACTS.clone()) };
+    contract_pass.run_pass(tcx, &mut new_body);
+    let loop_contract_pass = unsafe { LoopContractPass::new(tcx, LOOP_CONTRACTS.clone()) };
+    loop_contract_pass.run_pass(tcx, &mut new_body);
+    unsafe {
+        APPLIED_STUBS.extend(stub_pass.applied_stubs());
+        APPLIED_STUBS.extend(contract_pass.applied_contracts());
+        APPLIED_STUBS.extend(loop_contract_pass.applied_contracts());
+    }
     return tcx.arena.alloc(new_body);
 }
 
@@ -6,8 +6,10 @@ extern crate rustc_driver;
 extern crate rustc_errors;
 extern crate rustc_hir;
 extern crate rustc_index;
+extern crate rustc_interface;
 extern crate rustc_metadata;
 extern crate rustc_middle;
+extern crate rustc_query_system;
 extern crate rustc_session;
 extern crate rustc_span;
 extern crate rustc_target;
@@ -60,6 +62,7 @@ pub use rustc_middle::ty::print::FmtPrinter;
 pub use rustc_middle::ty::print::Printer;
 pub use rustc_middle::ty::query::Providers;
 pub use rustc_middle::ty::subst::InternalSubsts;
+pub use rustc_query_system::ich::StableHashingContext;
 // TODO: Expand self.
 pub use rustc_middle::ty::{
     self, AdtDef, Const, ConstKind, FloatTy, Instance, InstanceDef, IntTy, List, PolyFnSig, Ty,
@@ -70,7 +73,7 @@ pub use rustc_session::cstore::DllImport;
 pub use rustc_session::cstore::MetadataLoader;
 pub use rustc_session::cstore::MetadataLoaderDyn;
 pub use rustc_session::Session;
-pub use rustc_span::def_id::{DefId, LOCAL_CRATE};
+pub use rustc_span::def_id::{DefId, DefPathHash, LOCAL_CRATE};
 pub use rustc_span::Span;
 pub use rustc_span::Symbol;
 pub use rustc_span::DUMMY_SP;
@@ -81,3 +84,136 @@ pub use rustc_target::abi::{
 pub use rustc_target::spec::abi::Abi as SpecAbi;
 pub use rustc_target::spec::PanicStrategy;
 pub use rustc_target::spec::Target;
+
+use rustc_data_structures::fx::FxHashSet;
+use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
+use rustc_middle::mir::visit::Visitor as MirVisitor;
+use rustc_middle::mir::{Location, TerminatorKind};
+use rustc_middle::ty::TyKind;
+
+/// Compute a fingerprint for `def_id`'s optimized MIR that is stable across crates and
+/// recompiles, so it can be used to key a cache of verification results: if the fingerprint of a
+/// harness and everything it reaches hasn't changed, we already know the answer.
+///
+/// Critical invariant: the hash incorporates the `DefPathHash` of every function *transitively*
+/// reached from the body, not just its direct callees, so a change two calls deep still
+/// invalidates the top-level cache entry even though neither `def_id`'s own MIR nor its direct
+/// callees' `DefId`s changed.
+pub fn stable_mir_fingerprint<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> (DefPathHash, u128) {
+    let def_path_hash = tcx.def_path_hash(def_id);
+    let body = tcx.optimized_mir(def_id);
+    let fingerprint = tcx.with_stable_hashing_context(|mut hcx| {
+        let mut hasher = StableHasher::new();
+        body.hash_stable(&mut hcx, &mut hasher);
+        def_path_hash.hash_stable(&mut hcx, &mut hasher);
+        // Sorted by `DefPathHash` (stable across crates/recompiles, unlike `DefId`'s raw index)
+        // so the fold order -- and therefore the resulting fingerprint -- doesn't depend on the
+        // arbitrary order a `FxHashSet` happens to iterate in.
+        let mut reached: Vec<_> = transitive_callees(tcx, def_id, body)
+            .into_iter()
+            .map(|callee| tcx.def_path_hash(callee))
+            .collect();
+        reached.sort();
+        for callee_hash in reached {
+            callee_hash.hash_stable(&mut hcx, &mut hasher);
+        }
+        let (upper, lower) = hasher.finalize();
+        ((upper as u128) << 64) | lower as u128
+    });
+    (def_path_hash, fingerprint)
+}
+
+/// Every function transitively reachable from `body` (the already-fetched MIR of `root`),
+/// excluding `root` itself -- i.e. `root`'s direct callees, their callees, and so on. Tracks a
+/// visited set keyed by `DefId` so a recursive or mutually-recursive call graph terminates
+/// instead of looping forever.
+fn transitive_callees<'tcx>(tcx: TyCtxt<'tcx>, root: DefId, body: &Body<'tcx>) -> FxHashSet<DefId> {
+    let mut seen: FxHashSet<DefId> = FxHashSet::default();
+    seen.insert(root);
+    let mut worklist = callees(tcx, body);
+    while let Some(def_id) = worklist.pop() {
+        if !seen.insert(def_id) {
+            continue;
+        }
+        // A callee with no body available here (an intrinsic, an `extern` declaration, a trait
+        // method with no default) contributes nothing further to walk into.
+        if tcx.is_mir_available(def_id) {
+            worklist.extend(callees(tcx, tcx.optimized_mir(def_id)));
+        }
+    }
+    seen.remove(&root);
+    seen
+}
+
+/// Builds a replacement `Body` for a stubbed or contract-replaced function, given the `TyCtxt` of
+/// the compilation session that needs it (so the builder can allocate into the right arena).
+pub type BodyBuilder = for<'tcx> fn(TyCtxt<'tcx>, DefId) -> Body<'tcx>;
+
+/// Replacement bodies to install, keyed by the `DefPathHash` of the function they replace (rather
+/// than `DefId`, since a `DefPathHash` stays valid across crates and recompiles).
+pub type BodySubstitutions = FxHashMap<DefPathHash, BodyBuilder>;
+
+// TODO: This should go away once Kani becomes a driver instead of just a codegen backend, the
+// same way `OPTIMIZED_MIR_FN` in `rustc_codegen_rmc::mir_transform` should. `Providers` only
+// holds bare fn pointers, so the substitution map has to live behind a static until then.
+static mut MIR_SUBSTITUTIONS: Option<BodySubstitutions> = None;
+
+/// Register `substitutions` and install a wrapper around `optimized_mir`/`mir_for_ctfe` that
+/// consults it first and falls back to the default provider otherwise.
+///
+/// This is the plumbing Kani needs for function stubbing and contract replacement: verifying a
+/// caller against an abstract `requires`/`ensures` body instead of the real implementation. Call
+/// this from `Callbacks::config`/`override_queries` with the substitute bodies to install for this
+/// compilation session.
+pub fn override_mir_queries(providers: &mut Providers, substitutions: BodySubstitutions) {
+    unsafe {
+        MIR_SUBSTITUTIONS = Some(substitutions);
+    }
+    providers.optimized_mir = substitute_optimized_mir;
+    providers.mir_for_ctfe = substitute_mir_for_ctfe;
+}
+
+fn builder_for(def_id: DefId, tcx: TyCtxt<'_>) -> Option<BodyBuilder> {
+    let def_path_hash = tcx.def_path_hash(def_id);
+    unsafe { MIR_SUBSTITUTIONS.as_ref() }.and_then(|subs| subs.get(&def_path_hash).copied())
+}
+
+fn substitute_optimized_mir<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> &'tcx Body<'tcx> {
+    match builder_for(def_id, tcx) {
+        Some(build) => tcx.arena.alloc(build(tcx, def_id)),
+        None => rustc_interface::DEFAULT_QUERY_PROVIDERS.optimized_mir(tcx, def_id),
+    }
+}
+
+fn substitute_mir_for_ctfe<'tcx>(tcx: TyCtxt<'tcx>, def_id: DefId) -> &'tcx Body<'tcx> {
+    match builder_for(def_id, tcx) {
+        Some(build) => tcx.arena.alloc(build(tcx, def_id)),
+        None => rustc_interface::DEFAULT_QUERY_PROVIDERS.mir_for_ctfe(tcx, def_id),
+    }
+}
+
+/// Collect the `DefId` of every function called from `body`, without resolving generics. This is
+/// only used to decide what else must be stable for `body`'s fingerprint to stay stable, so an
+/// over-approximation (e.g. an unresolved trait method) is fine.
+fn callees<'tcx>(tcx: TyCtxt<'tcx>, body: &Body<'tcx>) -> Vec<DefId> {
+    struct CalleeCollector<'b, 'tcx> {
+        tcx: TyCtxt<'tcx>,
+        body: &'b Body<'tcx>,
+        callees: Vec<DefId>,
+    }
+
+    impl<'b, 'tcx> MirVisitor<'tcx> for CalleeCollector<'b, 'tcx> {
+        fn visit_terminator(&mut self, terminator: &Terminator<'tcx>, location: Location) {
+            if let TerminatorKind::Call { ref func, .. } = terminator.kind {
+                if let TyKind::FnDef(def_id, _) = func.ty(self.body, self.tcx).kind() {
+                    self.callees.push(*def_id);
+                }
+            }
+            self.super_terminator(terminator, location);
+        }
+    }
+
+    let mut collector = CalleeCollector { tcx, body, callees: vec![] };
+    collector.visit_body(body);
+    collector.callees
+}
@@ -12,7 +12,10 @@
 //!     - VTable methods for types that are coerced as unsized types.
 //!   - For every static, collect initializer and drop functions.
 //!
-//! We have kept this module agnostic of any Kani code in case we can contribute this back to rustc.
+//! We have kept this module agnostic of any Kani code in case we can contribute this back to
+//! rustc, with one exception: `MonoItemsCollector::visit_fn` consults
+//! `metadata_loader::cached_reachability` before walking an external-crate function's MIR, so a
+//! dependency compiled with Kani's sidecar mechanism doesn't need its call graph re-walked here.
 use rustc_data_structures::fingerprint::Fingerprint;
 use rustc_data_structures::fx::FxHashSet;
 use rustc_data_structures::stable_hasher::{HashStable, StableHasher};
@@ -27,14 +30,18 @@ use rustc_middle::span_bug;
 use rustc_middle::traits::{ImplSource, ImplSourceUserDefinedData};
 use rustc_middle::ty::adjustment::CustomCoerceUnsized;
 use rustc_middle::ty::adjustment::PointerCast;
+use rustc_middle::ty::print::with_no_trimmed_paths;
 use rustc_middle::ty::{
     self, Closure, ClosureKind, Const, ConstKind, Instance, InstanceDef, ParamEnv, TraitRef, Ty,
     TyCtxt, TyKind, TypeFoldable, VtblEntry,
 };
-use rustc_span::def_id::DefId;
+use rustc_span::def_id::{CrateNum, DefId};
 use rustc_span::source_map::DUMMY_SP;
+use rustc_span::Symbol;
 use tracing::{debug, debug_span, trace, warn};
 
+use crate::metadata_loader::cached_reachability;
+
 /// Collect all reachable items starting from the given starting points.
 pub fn collect_reachable_items<'tcx>(
     tcx: TyCtxt<'tcx>,
@@ -113,6 +120,14 @@ impl<'tcx> MonoItemsCollector<'tcx> {
     /// Visit a function and collect all mono-items reachable from its instructions.
     fn visit_fn(&mut self, instance: Instance<'tcx>) {
         let _guard = debug_span!("visit_fn", function=?instance).entered();
+        let def_id = instance.def_id();
+        if !def_id.is_local() {
+            if let Some(cached) = self.cached_reachable_items(def_id.krate) {
+                debug!(crate = ?def_id.krate, "reusing cached reachability instead of walking MIR");
+                self.queue.extend(cached.into_iter().filter(|item| !self.collected.contains(item)));
+                return;
+            }
+        }
         let body = self.tcx.instance_mir(instance.def);
         let mut collector =
             MonoItemsFnCollector { tcx: self.tcx, collected: FxHashSet::default(), instance, body };
@@ -120,6 +135,42 @@ impl<'tcx> MonoItemsCollector<'tcx> {
         self.queue.extend(collector.collected.iter().filter(|item| !self.collected.contains(item)));
     }
 
+    /// If `cnum`'s rlib has a reachability sidecar cached by `KaniMetadataLoader` (see
+    /// `metadata_loader`), resolve it into the `MonoItem`s it names, so `visit_fn` can reuse that
+    /// dependency's own precomputed reachable set instead of re-walking its MIR -- the "true
+    /// separate compilation" this module's doc comment promises. Returns `None` when there's
+    /// nothing cached (e.g. `KaniMetadataLoader` isn't installed as the session's `MetadataLoader`
+    /// in this trimmed checkout, or `cnum` predates the sidecar mechanism), so the caller falls
+    /// back to the normal MIR walk.
+    ///
+    /// Each cached entry is resolved with `Instance::mono`, the same non-generic assumption
+    /// `visit_static` below already makes for statics: a generic item in the cached set would need
+    /// the caller's actual substitutions to turn back into a concrete `Instance`, which the
+    /// sidecar doesn't carry, so such an item is silently skipped rather than collected wrong.
+    fn cached_reachable_items(&self, cnum: CrateNum) -> Option<Vec<MonoItem<'tcx>>> {
+        let rlib_path = self.tcx.used_crate_source(cnum).rlib.as_ref()?.0.clone();
+        let metadata = cached_reachability(&rlib_path)?;
+        Some(
+            metadata
+                .reachable()
+                .iter()
+                .filter_map(|&hash| {
+                    let def_id = self.tcx.def_path_hash_to_def_id(hash, &mut || {
+                        span_bug!(
+                            DUMMY_SP,
+                            "cached reachability sidecar named a DefPathHash with no matching \
+                             DefId in this session"
+                        )
+                    });
+                    if self.tcx.generics_of(def_id).requires_monomorphization(self.tcx) {
+                        return None;
+                    }
+                    Some(MonoItem::Fn(Instance::mono(self.tcx, def_id)))
+                })
+                .collect(),
+        )
+    }
+
     /// Visit a static object and collect drop / initialization functions.
     fn visit_static(&mut self, def_id: DefId) {
         let _guard = debug_span!("visit_static", ?def_id).entered();
@@ -206,6 +257,46 @@ impl<'a, 'tcx> MonoItemsFnCollector<'a, 'tcx> {
         self.collect_instance(instance, false, "vtable");
     }
 
+    /// Collect the vtable reached by a `dyn Sub -> dyn Super` trait-upcasting coercion.
+    ///
+    /// If the coercion only changes marker/auto traits (same principal on both sides) it is a
+    /// pure no-op that reuses the existing vtable pointer, so there is nothing new to collect.
+    /// Otherwise it is a real super-trait upcast: the child vtable's layout stores the parent
+    /// vtable pointer in one of its `VtblEntry::TraitVPtr` slots, so we just need to make sure the
+    /// parent vtable is emitted as a mono item for codegen to find it there.
+    fn collect_trait_upcast(
+        &mut self,
+        source_ty: Ty<'tcx>,
+        data_a: &'tcx ty::List<ty::Binder<'tcx, ty::ExistentialPredicate<'tcx>>>,
+        data_b: &'tcx ty::List<ty::Binder<'tcx, ty::ExistentialPredicate<'tcx>>>,
+    ) {
+        if data_a.principal_def_id() == data_b.principal_def_id() {
+            trace!("collect_trait_upcast: no-op (auto-trait only)");
+            return;
+        }
+        if let Some(target_principal) = data_b.principal() {
+            let vtable_id = self.tcx.vtable_allocation((source_ty, Some(target_principal)));
+            self.collected.extend(collect_alloc_items(self.tcx, vtable_id));
+        }
+    }
+
+    /// Collect the vtable backing a `?Sized` value's metadata, for intrinsics like
+    /// `size_of_val`/`min_align_of_val` that read the size/align words directly out of it at
+    /// runtime. `ptr_ty` is the (possibly reference/pointer) type of the argument passed in.
+    ///
+    /// A `Slice`/`Str` tail needs no vtable since its metadata is just the element count, so that
+    /// case is a no-op.
+    fn collect_metadata_source(&mut self, ptr_ty: Ty<'tcx>) {
+        let pointee_ty = ptr_ty.builtin_deref(true).map_or(ptr_ty, |ty_and_mut| ty_and_mut.ty);
+        if let TyKind::Dynamic(data, ..) = pointee_ty.kind() {
+            if let Some(principal) = data.principal() {
+                trace!(?pointee_ty, "collect_metadata_source");
+                let vtable_id = self.tcx.vtable_allocation((pointee_ty, Some(principal)));
+                self.collected.extend(collect_alloc_items(self.tcx, vtable_id));
+            }
+        }
+    }
+
     /// Collect an instance depending on how it is used (invoked directly or via fn_ptr).
     fn collect_instance(&mut self, instance: Instance<'tcx>, is_direct_call: bool, from: &str) {
         trace!(from, ?instance, ?is_direct_call, "collect_instance");
@@ -279,14 +370,24 @@ impl<'a, 'tcx> MirVisitor<'tcx> for MonoItemsFnCollector<'a, 'tcx> {
         match *rvalue {
             Rvalue::Cast(CastKind::Pointer(PointerCast::Unsize), ref operand, target) => {
                 warn!("visit_rvalue cast 1");
-                // Check if the conversion include casting a concrete type to a trait type.
-                // If so, collect items from the impl `Trait for Concrete {}`.
                 let target_ty = self.monomorphize(target);
                 let source_ty = self.monomorphize(operand.ty(self.body, self.tcx));
-                let (src_inner, dst_inner) = extract_trait_casting(self.tcx, source_ty, target_ty);
-                if !src_inner.is_trait() && dst_inner.is_trait() {
-                    warn!(concrete_ty=?src_inner, trait_ty=?dst_inner, "collect_vtable_methods");
-                    self.collect_vtable_methods(src_inner, dst_inner);
+                if let (TyKind::Dynamic(data_a, ..), TyKind::Dynamic(data_b, ..)) =
+                    (source_ty.kind(), target_ty.kind())
+                {
+                    // `dyn Sub -> dyn Super` trait-upcasting coercion: both sides are already
+                    // `Dynamic`, so there is no concrete type to hand to `collect_vtable_methods`.
+                    warn!(?source_ty, ?target_ty, "collect_trait_upcast");
+                    self.collect_trait_upcast(source_ty, *data_a, *data_b);
+                } else {
+                    // Check if the conversion include casting a concrete type to a trait type.
+                    // If so, collect items from the impl `Trait for Concrete {}`.
+                    let (src_inner, dst_inner) =
+                        extract_trait_casting(self.tcx, source_ty, target_ty);
+                    if !src_inner.is_trait() && dst_inner.is_trait() {
+                        warn!(concrete_ty=?src_inner, trait_ty=?dst_inner, "collect_vtable_methods");
+                        self.collect_vtable_methods(src_inner, dst_inner);
+                    }
                 }
             }
             Rvalue::Cast(CastKind::Pointer(PointerCast::ReifyFnPointer), ref operand, _) => {
@@ -390,7 +491,7 @@ impl<'a, 'tcx> MirVisitor<'tcx> for MonoItemsFnCollector<'a, 'tcx> {
 
         let tcx = self.tcx;
         match terminator.kind {
-            TerminatorKind::Call { ref func, .. } => {
+            TerminatorKind::Call { ref func, ref args, .. } => {
                 let callee_ty = func.ty(self.body, tcx);
                 let fn_ty = self.monomorphize(callee_ty);
                 if let TyKind::FnDef(def_id, substs) = *fn_ty.kind() {
@@ -399,6 +500,27 @@ impl<'a, 'tcx> MirVisitor<'tcx> for MonoItemsFnCollector<'a, 'tcx> {
                             .unwrap()
                             .unwrap();
                     self.collect_instance(instance, true, "Call");
+                    if is_size_or_align_of_val(tcx, def_id) {
+                        if let Some(arg) = args.get(0) {
+                            let arg_ty = self.monomorphize(arg.ty(self.body, tcx));
+                            self.collect_metadata_source(arg_ty);
+                        }
+                    }
+                    if let Some(metadata_fn) = metadata_reconstruction_fn(tcx, def_id) {
+                        // `ptr::metadata` reads `T` off of the pointer argument; `from_raw_parts`
+                        // reconstructs a pointer to `T` and only has it as a generic parameter.
+                        let pointee_ty = match metadata_fn {
+                            MetadataFn::Metadata => {
+                                args.get(0).map(|arg| self.monomorphize(arg.ty(self.body, tcx)))
+                            }
+                            MetadataFn::FromRawParts => {
+                                substs.types().next().map(|ty| self.monomorphize(ty))
+                            }
+                        };
+                        if let Some(pointee_ty) = pointee_ty {
+                            self.collect_metadata_source(pointee_ty);
+                        }
+                    }
                 } else {
                     assert!(
                         matches!(fn_ty.kind(), TyKind::FnPtr(..)),
@@ -485,6 +607,82 @@ fn should_codegen_locally<'tcx>(tcx: TyCtxt<'tcx>, instance: &Instance<'tcx>) ->
     }
 }
 
+/// Diagnostic item names `is_size_or_align_of_val`/`metadata_reconstruction_fn` look up before
+/// falling back to path-string matching, mirroring the lookup
+/// `fn_call_abstractions::get_rmc_definitions` already uses one file over for RMC's own
+/// abstraction functions. Unlike that case, these names aren't tagged on anything in this repo --
+/// they'd need a `#[rustc_diagnostic_item = "..."]` attribute added to the real `size_of_val`/
+/// `align_of_val`/`ptr::metadata`/`ptr::from_raw_parts[_mut]` definitions in rustc's actual
+/// standard library, which isn't part of this trimmed checkout at all (unlike, say, the RMC
+/// library crate, which is present here even if trimmed down). So today `get_diagnostic_item`
+/// always returns `None` for these and the path-string list below is still what actually detects
+/// these calls; the diagnostic-item names are wired in ahead of time so that the moment std
+/// carries the tags, these two functions pick them up with no further change here.
+const SIZE_OF_VAL_DIAGNOSTIC_ITEMS: &[&str] = &["size_of_val", "align_of_val"];
+const PTR_METADATA_DIAGNOSTIC_ITEM: &str = "ptr_metadata";
+const PTR_FROM_RAW_PARTS_DIAGNOSTIC_ITEM: &str = "ptr_from_raw_parts";
+
+/// Returns whether `def_id` is one of the `size_of_val`/`min_align_of_val` family, which reads
+/// the size/align words directly out of a trait object's vtable at runtime rather than going
+/// through a path the collector would otherwise see (a static or a constant).
+fn is_size_or_align_of_val(tcx: TyCtxt, def_id: DefId) -> bool {
+    const NAMES: &[&str] = &[
+        "core::intrinsics::size_of_val",
+        "core::mem::size_of_val",
+        "std::mem::size_of_val",
+        "core::intrinsics::min_align_of_val",
+        "core::mem::align_of_val",
+        "std::mem::align_of_val",
+    ];
+    if SIZE_OF_VAL_DIAGNOSTIC_ITEMS
+        .iter()
+        .any(|name| tcx.get_diagnostic_item(Symbol::intern(name)) == Some(def_id))
+    {
+        return true;
+    }
+    let name = with_no_trimmed_paths(|| tcx.def_path_str(def_id));
+    NAMES.contains(&name.as_str())
+}
+
+/// Which of the pointee-metadata reconstruction APIs a call targets.
+#[derive(Debug, Clone, Copy)]
+enum MetadataFn {
+    /// `core::ptr::metadata`: pulls `DynMetadata<Dyn>` (or another metadata kind) out of a fat
+    /// pointer. The pointee type is the pointer argument's pointee.
+    Metadata,
+    /// `core::ptr::from_raw_parts[_mut]`: rebuilds a fat pointer from an address and metadata.
+    /// The pointee type only appears as the function's generic parameter, not in any argument.
+    FromRawParts,
+}
+
+/// Returns which pointee-metadata reconstruction API `def_id` is, if any. A harness that stores or
+/// threads a `DynMetadata<dyn Trait>` value and later rebuilds a fat pointer with it needs the
+/// vtable the metadata points at to still be reachable, even though no `Unsize` cast appears in
+/// the MIR for either half of that round-trip.
+fn metadata_reconstruction_fn(tcx: TyCtxt, def_id: DefId) -> Option<MetadataFn> {
+    const METADATA: &[&str] = &["core::ptr::metadata", "std::ptr::metadata"];
+    const FROM_RAW_PARTS: &[&str] = &[
+        "core::ptr::from_raw_parts",
+        "std::ptr::from_raw_parts",
+        "core::ptr::from_raw_parts_mut",
+        "std::ptr::from_raw_parts_mut",
+    ];
+    if tcx.get_diagnostic_item(Symbol::intern(PTR_METADATA_DIAGNOSTIC_ITEM)) == Some(def_id) {
+        return Some(MetadataFn::Metadata);
+    }
+    if tcx.get_diagnostic_item(Symbol::intern(PTR_FROM_RAW_PARTS_DIAGNOSTIC_ITEM)) == Some(def_id) {
+        return Some(MetadataFn::FromRawParts);
+    }
+    let name = with_no_trimmed_paths(|| tcx.def_path_str(def_id));
+    if METADATA.contains(&name.as_str()) {
+        Some(MetadataFn::Metadata)
+    } else if FROM_RAW_PARTS.contains(&name.as_str()) {
+        Some(MetadataFn::FromRawParts)
+    } else {
+        None
+    }
+}
+
 /// Extract the pair (from_ty, to_ty) for a unsized cast.
 ///
 /// For example, if `&u8` is being converted to `&dyn Debug`, this method would return:
@@ -610,13 +808,35 @@ fn find_vtable_types_for_unsizing<'tcx>(
     }
 }
 
+/// Locate the field that turns `source_ty` into `target_ty` for a custom unsizing coercion.
+///
+/// Normally this comes from the ADT's `CoerceUnsized` impl. But a type can also opt into being a
+/// valid method receiver for a trait object (`self: MyRc<Self>`) by implementing `DispatchFromDyn`
+/// instead, e.g. to support arbitrary self types without also supporting `as` coercions. Method
+/// dispatch through such a receiver needs the same field located, so fall back to the
+/// `DispatchFromDyn` impl when there is no `CoerceUnsized` one.
 fn custom_coerce_unsize_info<'tcx>(
     tcx: TyCtxt<'tcx>,
     source_ty: Ty<'tcx>,
     target_ty: Ty<'tcx>,
 ) -> CustomCoerceUnsized {
-    let def_id = tcx.require_lang_item(LangItem::CoerceUnsized, None);
+    coerce_index_via_lang_item(tcx, LangItem::CoerceUnsized, source_ty, target_ty)
+        .or_else(|| coerce_index_via_lang_item(tcx, LangItem::DispatchFromDyn, source_ty, target_ty))
+        .unwrap_or_else(|| {
+            unreachable!(
+                "Could not find `CoerceUnsized` or `DispatchFromDyn` impl for {:?} -> {:?}",
+                source_ty, target_ty
+            )
+        })
+}
 
+fn coerce_index_via_lang_item<'tcx>(
+    tcx: TyCtxt<'tcx>,
+    lang_item: LangItem,
+    source_ty: Ty<'tcx>,
+    target_ty: Ty<'tcx>,
+) -> Option<CustomCoerceUnsized> {
+    let def_id = tcx.lang_items().get(lang_item)?;
     let trait_ref = ty::Binder::dummy(TraitRef {
         def_id,
         substs: tcx.mk_substs_trait(source_ty, &[target_ty.into()]),
@@ -624,11 +844,9 @@ fn custom_coerce_unsize_info<'tcx>(
 
     match tcx.codegen_select_candidate((ParamEnv::reveal_all(), trait_ref)) {
         Ok(ImplSource::UserDefined(ImplSourceUserDefinedData { impl_def_id, .. })) => {
-            tcx.coerce_unsized_info(impl_def_id).custom_kind.unwrap()
-        }
-        impl_source => {
-            unreachable!("invalid `CoerceUnsized` impl_source: {:?}", impl_source);
+            tcx.coerce_unsized_info(impl_def_id).custom_kind
         }
+        _ => None,
     }
 }
 
@@ -0,0 +1,137 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! This module wraps rustc's `DefaultMetadataLoader` so that, alongside the normal rlib metadata,
+//! Kani writes a sidecar file with the crate's reachable `MonoItem`/`DefPathHash` set. When that
+//! rlib is later pulled in as a dependency, we read the sidecar back instead of re-walking all of
+//! the dependency's MIR, giving Kani true separate compilation.
+//!
+//! `KaniMetadataLoader::get_rlib_metadata` (below) reads a dependency's sidecar as soon as rustc
+//! asks to load that rlib, so [`read_sidecar`] has a real caller. Two pieces of this still need
+//! to live outside this trimmed checkout, in the `CodegenBackend` impl this crate doesn't include
+//! here: installing `KaniMetadataLoader` itself as the session's `MetadataLoader` (instead of
+//! rustc's default), and calling [`write_sidecar`] once at the end of codegen with this crate's
+//! own `ReachabilityMetadata`.
+
+use rustc_data_structures::sync::MetadataRef;
+use rustc_macros::{Decodable, Encodable};
+use rustc_middle::mir::mono::MonoItem;
+use rustc_middle::ty::TyCtxt;
+use rustc_serialize::{opaque, Decodable as _, Encodable as _};
+use rustc_session::cstore::MetadataLoader;
+use rustc_session::output::filename_for_metadata;
+use rustc_session::Session;
+use rustc_span::def_id::DefPathHash;
+use rustc_target::spec::Target;
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+/// Bumped whenever the payload shape changes, so a blob written by an incompatible compiler is
+/// rejected outright rather than mis-decoded.
+const FORMAT_VERSION: u32 = 1;
+
+/// The extension used for Kani's sidecar metadata file, sitting next to the regular rlib.
+const SIDECAR_EXTENSION: &str = "kani-reachability.bin";
+
+/// The payload Kani stashes alongside a crate's rlib metadata.
+#[derive(Encodable, Decodable, Debug, Default, Clone)]
+pub struct ReachabilityMetadata {
+    format_version: u32,
+    /// Every `DefPathHash` this crate found reachable while compiling (functions, statics, and
+    /// the drop glue / vtables that implies).
+    reachable: Vec<DefPathHash>,
+}
+
+impl ReachabilityMetadata {
+    pub fn new(reachable: &[MonoItem<'_>], tcx: TyCtxt<'_>) -> Self {
+        let reachable = reachable
+            .iter()
+            .filter_map(|item| match item {
+                MonoItem::Fn(instance) => Some(tcx.def_path_hash(instance.def_id())),
+                MonoItem::Static(def_id) => Some(tcx.def_path_hash(*def_id)),
+                MonoItem::GlobalAsm(_) => None,
+            })
+            .collect();
+        ReachabilityMetadata { format_version: FORMAT_VERSION, reachable }
+    }
+
+    /// The set of `DefPathHash`es this crate's compilation found reachable.
+    pub fn reachable(&self) -> &[DefPathHash] {
+        &self.reachable
+    }
+}
+
+/// Path to the sidecar file for a crate whose rlib metadata would be written to `metadata_path`.
+fn sidecar_path(metadata_path: &Path) -> PathBuf {
+    metadata_path.with_extension(SIDECAR_EXTENSION)
+}
+
+/// Write `metadata`'s sidecar file next to where its rlib metadata lives.
+pub fn write_sidecar(sess: &Session, crate_name: &str, metadata: &ReachabilityMetadata) -> io::Result<()> {
+    let metadata_path = filename_for_metadata(sess, crate_name, &sess.io.output_dir.clone().unwrap_or_default());
+    let mut encoder = opaque::FileEncoder::new(sidecar_path(&metadata_path))?;
+    metadata.encode(&mut encoder).unwrap();
+    encoder.flush()?;
+    Ok(())
+}
+
+/// Read the sidecar file next to `rlib_path`, if present. Returns `Ok(None)` when the rlib
+/// predates this mechanism (no sidecar was ever written) or the sidecar was produced by an
+/// incompatible compiler (format version mismatch), since both cases should be treated the same
+/// way as "we have no cached reachability data for this dependency" rather than an error.
+pub fn read_sidecar(rlib_path: &Path) -> io::Result<Option<ReachabilityMetadata>> {
+    let path = sidecar_path(rlib_path);
+    if !path.exists() {
+        return Ok(None);
+    }
+    let bytes = fs::read(&path)?;
+    let mut decoder = opaque::Decoder::new(&bytes, 0);
+    let metadata = ReachabilityMetadata::decode(&mut decoder);
+    Ok((metadata.format_version == FORMAT_VERSION).then_some(metadata))
+}
+
+/// Every dependency rlib's sidecar we've read so far this compilation session, keyed by the rlib
+/// path `get_rlib_metadata` was called with. `reachability::MonoItemsCollector::visit_fn` looks a
+/// dependency crate up here to skip re-walking its MIR, giving Kani the "true separate
+/// compilation" this module's doc comment promises.
+static CACHED_REACHABILITY: Mutex<Option<HashMap<PathBuf, ReachabilityMetadata>>> = Mutex::new(None);
+
+/// The sidecar read for `rlib_path` during this session, if `get_rlib_metadata` has been asked to
+/// load it.
+pub fn cached_reachability(rlib_path: &Path) -> Option<ReachabilityMetadata> {
+    CACHED_REACHABILITY.lock().unwrap().as_ref()?.get(rlib_path).cloned()
+}
+
+/// A `MetadataLoader` that delegates all the real rlib/dylib metadata reading to rustc's default
+/// loader. Kani's own reachability payload travels through a separate sidecar file (see
+/// [`write_sidecar`]/[`read_sidecar`]) rather than the rlib metadata section itself, so this
+/// wrapper only exists to give Kani a hook at the same place rustc looks up a `MetadataLoader`.
+#[derive(Debug, Default)]
+pub struct KaniMetadataLoader {
+    inner: rustc_codegen_ssa::back::metadata::DefaultMetadataLoader,
+}
+
+impl MetadataLoader for KaniMetadataLoader {
+    /// Every dependency rlib is loaded through here before codegen starts, which makes this the
+    /// right place to opportunistically read that rlib's reachability sidecar (if it has one) and
+    /// stash it in [`CACHED_REACHABILITY`] for `collect_reachable_items` to consult later, instead
+    /// of re-walking the dependency's MIR from scratch.
+    fn get_rlib_metadata(&self, target: &Target, filename: &Path) -> Result<MetadataRef, String> {
+        if let Ok(Some(metadata)) = read_sidecar(filename) {
+            CACHED_REACHABILITY
+                .lock()
+                .unwrap()
+                .get_or_insert_with(HashMap::new)
+                .insert(filename.to_path_buf(), metadata);
+        }
+        self.inner.get_rlib_metadata(target, filename)
+    }
+
+    fn get_dylib_metadata(&self, target: &Target, filename: &Path) -> Result<MetadataRef, String> {
+        self.inner.get_dylib_metadata(target, filename)
+    }
+}
@@ -0,0 +1,203 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+//! Turns a CBMC counterexample trace for a failed harness into a deterministic `#[test]` that
+//! replays the exact failing inputs without invoking CBMC again.
+//!
+//! This crate has no root module in this trimmed checkout (no `lib.rs`/`main.rs`, same as
+//! `fingerprint`/`goto_binary`/`list`/`project` here), so nothing declares `mod concrete_playback;`
+//! or actually calls [`save_playback_test`] with a real CBMC trace -- that caller belongs in the
+//! CBMC-invocation code (e.g. a `verify.rs`), which also isn't part of this checkout. Everything
+//! below is real, exercisable logic; only the last mile (reading CBMC's stdout and calling in)
+//! is the missing piece -- see the unit tests below, which exercise [`parse_counterexample`]
+//! directly against representative trace JSON instead of relying on that missing caller.
+
+use crate::project::{playback_divergence_comment, Project};
+use anyhow::Result;
+use kani_metadata::HarnessMetadata;
+use serde_json::Value;
+use std::fs;
+use std::path::PathBuf;
+
+/// One `kani::any::<T>()` call's concrete value, in the order CBMC assigned it during the trace.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConcreteValue {
+    pub bytes: Vec<u8>,
+}
+
+/// Walk a CBMC `--trace --json-ui` result (the `[{"result": [{"trace": [...]}]}]` shape CBMC
+/// documents) and pull out every nondet-input assignment's concrete bytes, in assignment order --
+/// which matches the order the harness's `kani::any()` calls ran in, since CBMC assigns one
+/// nondet symbol per call as it executes the trace.
+///
+/// Only understands steps [`is_nondet_input_assignment`] accepts, whose `"value"` object carries
+/// a `"binary"` bitstring; any other step kind (function calls, `"input"`/`"output"` steps, a
+/// trace shape from a CBMC version this wasn't built against) is skipped rather than treated as
+/// an error.
+pub fn parse_counterexample(trace_json: &Value) -> Vec<ConcreteValue> {
+    let mut values = vec![];
+    let Some(messages) = trace_json.as_array() else {
+        return values;
+    };
+    for message in messages {
+        let Some(results) = message.get("result").and_then(Value::as_array) else { continue };
+        for result in results {
+            let Some(steps) = result.get("trace").and_then(Value::as_array) else { continue };
+            for step in steps {
+                if !is_nondet_input_assignment(step) {
+                    continue;
+                }
+                let Some(binary) =
+                    step.get("value").and_then(|value| value.get("binary")).and_then(Value::as_str)
+                else {
+                    continue;
+                };
+                if let Some(bytes) = binary_str_to_bytes(binary) {
+                    values.push(ConcreteValue { bytes });
+                }
+            }
+        }
+    }
+    values
+}
+
+/// Whether `step` (one element of a CBMC `--trace --json-ui` step list) is an assignment that
+/// introduced one of the harness's own `kani::any()` inputs, as opposed to an ordinary
+/// intermediate assignment CBMC's trace also reports for every other statement it executes.
+///
+/// CBMC marks every step it considers internal bookkeeping -- spilled temporaries, short-circuit
+/// helpers, and other compiler-introduced plumbing -- with `"hidden": true`; a harness's actual
+/// nondet inputs are never hidden, so this rejects the hidden ones. It does *not* further narrow
+/// "non-hidden assignment" down to "specifically a `kani::any()` call": doing that precisely needs
+/// the exact nondet-symbol naming convention Kani's own codegen backend assigns these variables,
+/// and the part of `codegen_cprover_gotoc` that actually lowers `kani::any()` to a GOTO nondet
+/// expression isn't part of this trimmed checkout, so this can still pick up an unrelated
+/// non-hidden local a harness happens to declare between `any()` calls.
+fn is_nondet_input_assignment(step: &Value) -> bool {
+    step.get("stepType").and_then(Value::as_str) == Some("assignment")
+        && step.get("hidden").and_then(Value::as_bool) != Some(true)
+}
+
+/// Convert a CBMC bitstring (MSB-first, length a multiple of 8) into bytes, MSB-first within each
+/// byte -- the order `kani::concrete_playback_run`'s byte-vector argument expects. Returns `None`
+/// for a bitstring whose length isn't a whole number of bytes, or that contains anything besides
+/// `'0'`/`'1'`, rather than silently truncating or mis-decoding it.
+fn binary_str_to_bytes(binary: &str) -> Option<Vec<u8>> {
+    if binary.is_empty() || binary.len() % 8 != 0 {
+        return None;
+    }
+    binary
+        .as_bytes()
+        .chunks(8)
+        .map(|chunk| {
+            let mut byte = 0u8;
+            for &bit in chunk {
+                byte <<= 1;
+                match bit {
+                    b'0' => {}
+                    b'1' => byte |= 1,
+                    _ => return None,
+                }
+            }
+            Some(byte)
+        })
+        .collect()
+}
+
+/// Generate the source for a `#[test]` that replays `values` against `harness`, in the shape
+/// `kani::concrete_playback_run` expects: one `Vec<u8>` per recorded nondet value, fed back in the
+/// same order they were consumed while verifying the harness.
+pub fn generate_playback_source(
+    harness: &HarnessMetadata,
+    values: &[ConcreteValue],
+    replaced_functions: &[String],
+) -> String {
+    let mut src = String::new();
+    if let Some(comment) = playback_divergence_comment(replaced_functions) {
+        src.push_str(&comment);
+    }
+    src.push_str("#[test]\n");
+    src.push_str(&format!("fn {}_concrete_playback() {{\n", harness.mangled_name));
+    src.push_str("    let concrete_vals: Vec<Vec<u8>> = vec![\n");
+    for value in values {
+        src.push_str(&format!("        vec!{:?},\n", value.bytes));
+    }
+    src.push_str("    ];\n");
+    src.push_str(&format!(
+        "    kani::concrete_playback_run(concrete_vals, {});\n",
+        harness.pretty_name,
+    ));
+    src.push_str("}\n");
+    src
+}
+
+/// Parse `trace_json`, generate the replay test, and write it to `harness`'s playback file
+/// ([`Project::harness_playback_file`]). Returns the path written.
+pub fn save_playback_test(
+    project: &Project,
+    harness: &HarnessMetadata,
+    trace_json: &Value,
+    replaced_functions: &[String],
+) -> Result<PathBuf> {
+    let values = parse_counterexample(trace_json);
+    let source = generate_playback_source(harness, &values, replaced_functions);
+    let path = project.harness_playback_file(harness);
+    if let Some(parent) = path.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(&path, source)?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    /// A representative CBMC `--trace --json-ui` result: two harness-visible nondet assignments
+    /// (each a `u8` read as a one-byte `kani::any()`; the second has no `"hidden"` key at all,
+    /// which counts as not hidden), one hidden compiler-introduced temporary assignment in between
+    /// (which also happens to carry a `"binary"` value, so the filter -- not the missing
+    /// `"value"`/`"binary"` fields -- is what has to reject it), and one non-assignment step.
+    fn sample_trace() -> Value {
+        json!([{
+            "result": [{
+                "trace": [
+                    {
+                        "stepType": "assignment",
+                        "hidden": false,
+                        "value": { "binary": "00000101" }
+                    },
+                    {
+                        "stepType": "assignment",
+                        "hidden": true,
+                        "value": { "binary": "11111111" }
+                    },
+                    {
+                        "stepType": "function-call"
+                    },
+                    {
+                        "stepType": "assignment",
+                        "value": { "binary": "00000111" }
+                    }
+                ]
+            }]
+        }])
+    }
+
+    #[test]
+    fn parses_only_visible_assignments_in_order() {
+        let values = parse_counterexample(&sample_trace());
+        assert_eq!(values, vec![
+            ConcreteValue { bytes: vec![0b0000_0101] },
+            ConcreteValue { bytes: vec![0b0000_0111] },
+        ]);
+    }
+
+    #[test]
+    fn binary_str_to_bytes_rejects_malformed_bitstrings() {
+        assert_eq!(binary_str_to_bytes(""), None);
+        assert_eq!(binary_str_to_bytes("0000000"), None); // not a multiple of 8
+        assert_eq!(binary_str_to_bytes("0000000x"), None); // not '0'/'1'
+        assert_eq!(binary_str_to_bytes("00000101"), Some(vec![0b0000_0101]));
+    }
+}
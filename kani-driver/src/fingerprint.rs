@@ -0,0 +1,84 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+//! Content-hash fingerprints for verification `Artifact`s, so repeated `kani` runs on a large
+//! workspace only relink (and reverify) the crates whose MIR actually changed.
+//!
+//! This mirrors the `cargo:rerun-if-changed` approach the compiler's own `build.rs` uses to skip
+//! recompiling unchanged crates, just applied one layer up: instead of deciding whether to
+//! recompile a crate, we decide whether to re-link a `Goto` artifact out of its `SymTabGoto`
+//! inputs.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::hash_map::DefaultHasher;
+use std::collections::BTreeMap;
+use std::fs;
+use std::hash::{Hash, Hasher};
+use std::path::{Path, PathBuf};
+
+const FINGERPRINTS_FILE: &str = ".kani-fingerprints.json";
+
+/// The content hash of an artifact, plus the content hashes of the inputs that produced it (if
+/// any), recorded the last time we built it.
+#[derive(Clone, Debug, Default, Serialize, Deserialize, PartialEq, Eq)]
+pub struct ArtifactFingerprint {
+    pub content_hash: u64,
+    pub input_hashes: Vec<u64>,
+}
+
+/// All fingerprints recorded for a project's output directory, persisted as a single sidecar
+/// file alongside `outdir` so they survive between `kani` invocations.
+#[derive(Clone, Debug, Default, Serialize, Deserialize)]
+pub struct Fingerprints {
+    by_artifact: BTreeMap<PathBuf, ArtifactFingerprint>,
+}
+
+impl Fingerprints {
+    /// Load the fingerprints recorded for `outdir`, or an empty set if this is the first build.
+    pub fn load(outdir: &Path) -> Fingerprints {
+        let path = outdir.join(FINGERPRINTS_FILE);
+        fs::read_to_string(&path)
+            .ok()
+            .and_then(|contents| serde_json::from_str(&contents).ok())
+            .unwrap_or_default()
+    }
+
+    /// Persist these fingerprints to `outdir`, overwriting whatever was recorded before.
+    pub fn save(&self, outdir: &Path) -> Result<()> {
+        let path = outdir.join(FINGERPRINTS_FILE);
+        let contents = serde_json::to_string(self)?;
+        fs::write(&path, contents)
+            .with_context(|| format!("Failed to write fingerprints to `{}`", path.display()))
+    }
+
+    /// Record the fingerprint for `artifact`, given the content hashes of the inputs that
+    /// produced it (empty if it wasn't derived from other artifacts).
+    pub fn record(&mut self, artifact: &Path, input_hashes: Vec<u64>) -> Result<()> {
+        let content_hash = hash_file(artifact)?;
+        self.by_artifact
+            .insert(artifact.to_path_buf(), ArtifactFingerprint { content_hash, input_hashes });
+        Ok(())
+    }
+
+    /// Whether `artifact` is up to date: it exists, its on-disk content still matches the hash we
+    /// last recorded for it, and `input_hashes` (the current content hashes of whatever would
+    /// produce it) match what we recorded the last time we built it.
+    pub fn is_up_to_date(&self, artifact: &Path, input_hashes: &[u64]) -> bool {
+        match self.by_artifact.get(artifact) {
+            Some(recorded) if recorded.input_hashes == input_hashes => {
+                matches!(hash_file(artifact), Ok(hash) if hash == recorded.content_hash)
+            }
+            _ => false,
+        }
+    }
+}
+
+/// Hash the content of the file at `path`. Not cryptographic -- this only needs to detect that a
+/// rebuild produced different bytes, not resist a malicious adversary.
+pub fn hash_file(path: &Path) -> Result<u64> {
+    let bytes = fs::read(path)
+        .with_context(|| format!("Failed to read `{}` to compute its fingerprint", path.display()))?;
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    Ok(hasher.finish())
+}
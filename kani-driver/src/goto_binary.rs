@@ -0,0 +1,272 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+//! A pure-Rust reader/writer for this project's *own* symbol-table container format, used to
+//! merge per-crate `SymTabGoto` artifacts into the final `Goto` artifact in-process instead of
+//! shelling out to an external linker tool.
+//!
+//! Despite the name, this is **not** a compatible implementation of CBMC's own goto-binary v5
+//! format: the magic number, version, and string-table/irep layout below are this module's own
+//! invention, not CBMC's documented wire format (we don't even attempt to round-trip every irep
+//! feature CBMC's format supports -- only what's needed to merge symbol tables and to let tests
+//! inspect the result). A file this module writes can be read back by this module, but it is not
+//! something the real `cbmc`/`goto-cc` toolchain can consume, and a genuine CBMC-written goto
+//! binary is not something this module can read. Actually producing CBMC-consumable output still
+//! needs either a faithful reimplementation of CBMC's real format or shelling out to its own
+//! linker; this module only replaces the in-process merge step between Kani's own artifacts.
+//!
+//! The format itself:
+//! - A 4-byte magic number and a version number.
+//! - A string table: every distinct irep string used anywhere in the file, written once and
+//!   referenced everywhere else by its index, so repeated strings (type/identifier names) aren't
+//!   duplicated.
+//! - The symbol table itself: one entry per symbol, each a name (string-table index) plus its
+//!   irep tree (also built out of string-table indices).
+
+use anyhow::{bail, Context, Result};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufReader, BufWriter, Read, Write};
+use std::path::Path;
+
+/// Magic number at the start of every goto-binary file, used to sanity-check the format before
+/// attempting to parse the rest.
+const GOTO_BINARY_MAGIC: u32 = 0x0badcafe;
+/// The only container version this module knows how to read/write.
+const GOTO_BINARY_VERSION: u16 = 5;
+
+/// A single node of CBMC's "irep" tree: an identifier, plus a list of named sub-ireps and a list
+/// of unnamed ones. This mirrors CBMC's own `irept`, simplified to what we need to merge symbol
+/// tables without losing information on round-trip.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct Irep {
+    pub id: String,
+    pub named_sub: Vec<(String, Irep)>,
+    pub sub: Vec<Irep>,
+}
+
+/// One entry of the symbol table: a symbol's name and its irep (type, value, flags, ...).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Symbol {
+    pub name: String,
+    pub irep: Irep,
+}
+
+/// The contents of a goto-binary file: every symbol it defines, in file order.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct SymbolTable {
+    pub symbols: Vec<Symbol>,
+}
+
+impl SymbolTable {
+    /// Read a symbol table out of a goto-binary file at `path`.
+    pub fn read(path: &Path) -> Result<SymbolTable> {
+        let file = File::open(path)
+            .with_context(|| format!("Failed to open goto binary `{}`", path.display()))?;
+        let mut reader = Reader { input: BufReader::new(file), strings: vec![] };
+        reader.read_symbol_table()
+    }
+
+    /// Write this symbol table to `path` as a goto-binary file.
+    pub fn write(&self, path: &Path) -> Result<()> {
+        let file = File::create(path)
+            .with_context(|| format!("Failed to create goto binary `{}`", path.display()))?;
+        let mut writer = Writer { output: BufWriter::new(file), strings: HashMap::default() };
+        writer.write_symbol_table(self)
+    }
+}
+
+/// Merge several symbol tables into one, the way an external linker would: later inputs' symbols
+/// override earlier ones with the same name (e.g. a crate's own definition of a function it also
+/// imports a declaration of from a dependency).
+pub fn link(inputs: &[SymbolTable]) -> SymbolTable {
+    let mut by_name: HashMap<String, Symbol> = HashMap::default();
+    let mut order: Vec<String> = vec![];
+    for table in inputs {
+        for symbol in &table.symbols {
+            if !by_name.contains_key(&symbol.name) {
+                order.push(symbol.name.clone());
+            }
+            by_name.insert(symbol.name.clone(), symbol.clone());
+        }
+    }
+    SymbolTable { symbols: order.into_iter().map(|name| by_name.remove(&name).unwrap()).collect() }
+}
+
+/// Read each `SymTabGoto` artifact in `inputs`, link them together, and write the result to
+/// `output` as a `Goto` artifact -- the in-process replacement for shelling out to an external
+/// linker.
+pub fn link_goto_binaries(inputs: &[impl AsRef<Path>], output: &Path) -> Result<()> {
+    let tables: Vec<SymbolTable> =
+        inputs.iter().map(|path| SymbolTable::read(path.as_ref())).collect::<Result<_>>()?;
+    link(&tables).write(output)
+}
+
+struct Writer<W: Write> {
+    output: W,
+    strings: HashMap<String, u32>,
+}
+
+impl<W: Write> Writer<W> {
+    fn write_symbol_table(&mut self, table: &SymbolTable) -> Result<()> {
+        // Intern every string used anywhere in the file before writing anything, so the string
+        // table (written first) and the ids it hands out are complete.
+        for symbol in &table.symbols {
+            self.intern(&symbol.name);
+            self.intern_irep(&symbol.irep);
+        }
+
+        self.output.write_all(&GOTO_BINARY_MAGIC.to_le_bytes())?;
+        self.output.write_all(&GOTO_BINARY_VERSION.to_le_bytes())?;
+
+        let mut ordered: Vec<(&String, u32)> =
+            self.strings.iter().map(|(s, id)| (s, *id)).collect();
+        ordered.sort_by_key(|(_, id)| *id);
+        self.write_u32(ordered.len() as u32)?;
+        for (string, _) in ordered {
+            self.write_string_raw(string)?;
+        }
+
+        self.write_u32(table.symbols.len() as u32)?;
+        for symbol in &table.symbols {
+            self.write_string_ref(&symbol.name)?;
+            self.write_irep(&symbol.irep)?;
+        }
+        Ok(())
+    }
+
+    fn intern(&mut self, s: &str) {
+        if !self.strings.contains_key(s) {
+            let id = self.strings.len() as u32;
+            self.strings.insert(s.to_string(), id);
+        }
+    }
+
+    fn intern_irep(&mut self, irep: &Irep) {
+        self.intern(&irep.id);
+        for (name, sub) in &irep.named_sub {
+            self.intern(name);
+            self.intern_irep(sub);
+        }
+        for sub in &irep.sub {
+            self.intern_irep(sub);
+        }
+    }
+
+    fn write_irep(&mut self, irep: &Irep) -> Result<()> {
+        self.write_string_ref(&irep.id)?;
+        self.write_u32(irep.named_sub.len() as u32)?;
+        for (name, sub) in &irep.named_sub {
+            self.write_string_ref(name)?;
+            self.write_irep(sub)?;
+        }
+        self.write_u32(irep.sub.len() as u32)?;
+        for sub in &irep.sub {
+            self.write_irep(sub)?;
+        }
+        Ok(())
+    }
+
+    fn write_string_ref(&mut self, s: &str) -> Result<()> {
+        let id = *self.strings.get(s).expect("string was interned before being written");
+        self.write_u32(id)
+    }
+
+    fn write_string_raw(&mut self, s: &str) -> Result<()> {
+        self.write_u32(s.len() as u32)?;
+        self.output.write_all(s.as_bytes())?;
+        Ok(())
+    }
+
+    fn write_u32(&mut self, v: u32) -> Result<()> {
+        Ok(self.output.write_all(&v.to_le_bytes())?)
+    }
+}
+
+/// Upper bound on how much capacity any single length-prefixed field below is allowed to
+/// pre-allocate up front, regardless of what the (possibly truncated or corrupt) file claims its
+/// length is. Genuine files with more elements than this still read fine -- the backing
+/// allocation just grows incrementally, the same as any other `Vec::push` loop -- this only stops
+/// a tiny malicious length prefix from demanding a multi-gigabyte allocation before a single byte
+/// of it has actually been read.
+const MAX_PREALLOC: usize = 4096;
+
+struct Reader<R: Read> {
+    input: R,
+    strings: Vec<String>,
+}
+
+impl<R: Read> Reader<R> {
+    fn read_symbol_table(&mut self) -> Result<SymbolTable> {
+        let magic = self.read_u32()?;
+        if magic != GOTO_BINARY_MAGIC {
+            bail!("Not a goto binary: bad magic number {:#x}", magic);
+        }
+        let version = self.read_u16()?;
+        if version != GOTO_BINARY_VERSION {
+            bail!("Unsupported goto binary version {version}, expected {GOTO_BINARY_VERSION}");
+        }
+
+        let string_count = self.read_u32()?;
+        let mut strings = Vec::with_capacity((string_count as usize).min(MAX_PREALLOC));
+        for _ in 0..string_count {
+            strings.push(self.read_string_raw()?);
+        }
+        self.strings = strings;
+
+        let symbol_count = self.read_u32()?;
+        let mut symbols = Vec::with_capacity((symbol_count as usize).min(MAX_PREALLOC));
+        for _ in 0..symbol_count {
+            let name = self.read_string_ref()?;
+            let irep = self.read_irep()?;
+            symbols.push(Symbol { name, irep });
+        }
+        Ok(SymbolTable { symbols })
+    }
+
+    fn read_irep(&mut self) -> Result<Irep> {
+        let id = self.read_string_ref()?;
+        let named_sub_count = self.read_u32()?;
+        let mut named_sub = Vec::with_capacity((named_sub_count as usize).min(MAX_PREALLOC));
+        for _ in 0..named_sub_count {
+            let name = self.read_string_ref()?;
+            let sub = self.read_irep()?;
+            named_sub.push((name, sub));
+        }
+        let sub_count = self.read_u32()?;
+        let mut sub = Vec::with_capacity((sub_count as usize).min(MAX_PREALLOC));
+        for _ in 0..sub_count {
+            sub.push(self.read_irep()?);
+        }
+        Ok(Irep { id, named_sub, sub })
+    }
+
+    fn read_string_ref(&mut self) -> Result<String> {
+        let id = self.read_u32()? as usize;
+        self.strings
+            .get(id)
+            .cloned()
+            .with_context(|| format!("String reference {id} out of range"))
+    }
+
+    fn read_string_raw(&mut self) -> Result<String> {
+        let len = self.read_u32()? as u64;
+        let mut buf = Vec::with_capacity((len as usize).min(MAX_PREALLOC));
+        (&mut self.input).take(len).read_to_end(&mut buf)?;
+        if buf.len() as u64 != len {
+            bail!("Truncated goto binary: expected a {len}-byte string, got {}", buf.len());
+        }
+        Ok(String::from_utf8(buf)?)
+    }
+
+    fn read_u32(&mut self) -> Result<u32> {
+        let mut buf = [0u8; 4];
+        self.input.read_exact(&mut buf)?;
+        Ok(u32::from_le_bytes(buf))
+    }
+
+    fn read_u16(&mut self) -> Result<u16> {
+        let mut buf = [0u8; 2];
+        self.input.read_exact(&mut buf)?;
+        Ok(u16::from_le_bytes(buf))
+    }
+}
@@ -0,0 +1,87 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+//! Implements the `kani list` subcommand: a fast inventory of every proof harness,
+//! `proof_for_contract` target, contract, and stub declared across the crates in a `Project`,
+//! without invoking CBMC.
+//!
+//! `contracts`/`stubs` below are read straight off `HarnessMetadata`, i.e. whatever the compiler
+//! wrote to the `.kani-metadata.json` file this `Project` was loaded from
+//! (`mir_transform::applied_stub_names`, fed by `mir_transform::take_applied_stubs`, is meant to
+//! populate those fields in the compiler process before that file is written) -- this module has
+//! no way to populate them itself, so an out-of-date compiler leaves both fields empty. Since an
+//! empty field is indistinguishable from "this harness genuinely has none", `render_table` prints
+//! `unknown` rather than a dash for it, so the table doesn't read as a confirmed absence.
+
+use crate::project::Project;
+use anyhow::Result;
+use kani_metadata::HarnessMetadata;
+use serde::Serialize;
+use std::path::PathBuf;
+
+/// One row of the `kani list` inventory: everything a user needs to decide whether to verify a
+/// given harness, without running it.
+#[derive(Serialize)]
+pub struct HarnessListing {
+    pub name: String,
+    pub file: PathBuf,
+    /// Functions this harness's contract applies to: the target of a
+    /// `#[kani::proof_for_contract(...)]` harness, plus any `requires`/`ensures`/`modifies`
+    /// clauses it pulls in.
+    pub contracts: Vec<String>,
+    /// Functions this harness replaces via `#[kani::stub(...)]`.
+    pub stubs: Vec<String>,
+}
+
+/// Output format for `kani list`.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ListFormat {
+    /// Human-readable table: harness name, file, contracts, stubs.
+    Table,
+    /// A single JSON array of `HarnessListing`, for CI and IDE tooling.
+    Json,
+}
+
+/// Build the listing for every harness in `project`, pulled straight from the metadata the
+/// compiler already recorded -- no CBMC invocation needed.
+pub fn list_harnesses(project: &Project) -> Vec<HarnessListing> {
+    project.get_all_harnesses().iter().map(|harness| harness_listing(harness)).collect()
+}
+
+fn harness_listing(harness: &HarnessMetadata) -> HarnessListing {
+    HarnessListing {
+        name: harness.pretty_name.clone(),
+        file: harness.original_file.clone().into(),
+        contracts: harness.contracts.clone(),
+        stubs: harness.stubs.clone(),
+    }
+}
+
+/// Render `listing` in the requested `format`.
+pub fn render(listing: &[HarnessListing], format: ListFormat) -> Result<String> {
+    match format {
+        ListFormat::Json => Ok(serde_json::to_string_pretty(listing)?),
+        ListFormat::Table => Ok(render_table(listing)),
+    }
+}
+
+fn render_table(listing: &[HarnessListing]) -> String {
+    let mut out = String::from("Harness\tFile\tContracts\tStubs\n");
+    for item in listing {
+        out.push_str(&format!(
+            "{}\t{}\t{}\t{}\n",
+            item.name,
+            item.file.display(),
+            join_or_unknown(&item.contracts),
+            join_or_unknown(&item.stubs),
+        ));
+    }
+    out
+}
+
+/// Render a contracts/stubs cell. An empty list here is ambiguous -- it means either "this
+/// harness genuinely has none" or "the compiler that produced this metadata never populated this
+/// field" (see this module's doc comment) -- and this module has no way to tell the two apart, so
+/// it reports `unknown` rather than a dash that would misleadingly read as the former.
+fn join_or_unknown(items: &[String]) -> String {
+    if items.is_empty() { "unknown".to_string() } else { items.join(",") }
+}
@@ -4,6 +4,8 @@
 //! The goal is to provide one project view independent on the build system (cargo / standalone
 //! rustc) and its configuration (e.g.: linker type).
 
+use crate::fingerprint::{hash_file, Fingerprints};
+use crate::goto_binary::link_goto_binaries;
 use crate::metadata::{from_json, mock_proof_harness};
 use crate::session::KaniSession;
 use crate::util::{crate_name, guess_rlib_name};
@@ -32,6 +34,9 @@ pub struct Project {
     pub outdir: PathBuf,
     /// The collection of artifacts kept as part of this project.
     artifacts: Vec<Artifact>,
+    /// Content-hash fingerprints recorded the last time each artifact was built, used to skip
+    /// re-linking (and by extension re-verifying) harnesses whose inputs haven't changed.
+    fingerprints: Fingerprints,
 }
 
 impl Project {
@@ -65,6 +70,63 @@ impl Project {
                     .map_or(true, |model_file| from_model(model_file, typ) == artifact.path)
         })
     }
+
+    /// Whether the harness's `Goto` artifact is up to date with respect to the `SymTabGoto`
+    /// inputs that would produce it, i.e. whether re-linking it would be a no-op.
+    ///
+    /// Returns `false` (conservatively forcing a re-link) when there's no recorded fingerprint, or
+    /// no `Goto`/`SymTabGoto` artifact to compare at all.
+    pub fn is_harness_up_to_date(&self, harness: &HarnessMetadata) -> bool {
+        let goto = self.get_harness_artifact(harness, ArtifactType::Goto);
+        let symtab = self.get_harness_artifact(harness, ArtifactType::SymTabGoto);
+        match (goto, symtab) {
+            (Some(goto), Some(symtab)) => match hash_file(symtab) {
+                Ok(input_hash) => self.fingerprints.is_up_to_date(goto, &[input_hash]),
+                Err(_) => false,
+            },
+            _ => false,
+        }
+    }
+
+    /// Compute the path where a concrete playback unit test should be written for `harness`.
+    ///
+    /// Unlike the other artifact types, `ConcretePlayback` artifacts aren't produced by the build:
+    /// they're generated lazily, only for harnesses whose verification failed, once the CBMC
+    /// counterexample has been parsed into a concrete byte assignment (see
+    /// `concrete_playback::save_playback_test`, the real caller of this function). So there's no
+    /// existing `Artifact` to look up here, only a path to write one to.
+    pub fn harness_playback_file(&self, harness: &HarnessMetadata) -> PathBuf {
+        match &harness.model_file {
+            Some(model_file) => from_model(model_file, ArtifactType::ConcretePlayback),
+            None => {
+                let mut path = self.outdir.join(&harness.mangled_name);
+                path.set_extension(&ArtifactType::ConcretePlayback);
+                path
+            }
+        }
+    }
+}
+
+/// Build the leading comment a concrete playback test gets when the harness it was generated from
+/// relied on a function-call abstraction -- an RMC built-in, a user stub, or a contract
+/// replacement -- for any of `replaced_functions`. Since the harness never called the real
+/// function(s), the replayed inputs only reproduce the abstraction's behavior, so the comment
+/// warns against over-trusting the test as a faithful reproduction of the original code.
+///
+/// Returns `None` when `replaced_functions` is empty, i.e. the harness didn't go through any
+/// abstraction and the generated test needs no disclaimer. Called from
+/// `concrete_playback::generate_playback_source` while assembling the rest of the test source.
+pub fn playback_divergence_comment(replaced_functions: &[String]) -> Option<String> {
+    if replaced_functions.is_empty() {
+        return None;
+    }
+    Some(format!(
+        "// WARNING: this test replays a proof that replaced the following function(s) with a \
+         stub or contract during verification: {}.\n\
+         // The recorded inputs only reproduce the *replacement's* behavior, not necessarily the \
+         real function's -- don't treat a pass here as proof the original code is correct.\n",
+        replaced_functions.join(", "),
+    ))
 }
 
 /// Create a path from the model path.
@@ -121,12 +183,20 @@ pub fn cargo_project(session: &KaniSession) -> Result<Project> {
     } else {
         // For the MIR Linker we know there is only one artifact per verification target. Use
         // that in our favor. This also covers the dry run mode.
+        let mut fingerprints = Fingerprints::load(&outputs.outdir);
         for meta_file in outputs.metadata {
-            // Link the artifact.
+            // Link the artifact, unless the symbol table that would produce it hasn't changed
+            // since the last time we linked it.
             let base_path = meta_file.parent().unwrap().join(meta_file.file_stem().unwrap());
             let symtab_out = base_path.with_extension(&ArtifactType::SymTabGoto);
             let goto = base_path.with_extension(&ArtifactType::Goto);
-            session.link_goto_binary(&[symtab_out], &goto)?;
+            if !dry_run {
+                let input_hash = hash_file(&symtab_out)?;
+                if !fingerprints.is_up_to_date(&goto, &[input_hash]) {
+                    link_goto_binaries(&[&symtab_out], &goto)?;
+                    fingerprints.record(&goto, vec![input_hash])?;
+                }
+            }
 
             // Store project information.
             let crate_metadata: KaniMetadata =
@@ -138,7 +208,10 @@ pub fn cargo_project(session: &KaniSession) -> Result<Project> {
             debug!(?crate_name, ?crate_metadata, "cargo_project");
             metadata.push(crate_metadata);
         }
-        Ok(Project { outdir: outputs.outdir, artifacts, metadata })
+        if !dry_run {
+            fingerprints.save(&outputs.outdir)?;
+        }
+        Ok(Project { outdir: outputs.outdir, artifacts, metadata, fingerprints })
     }
 }
 
@@ -197,9 +270,15 @@ impl<'a> StandaloneProjectBuilder<'a> {
         let goto = self.artifact(ArtifactType::Goto);
 
         let dry_run = self.session.args.dry_run;
-        if dry_run || symtab_out.exists() {
-            debug!(?symtab_out, "build link");
-            self.session.link_goto_binary(&[symtab_out.to_path_buf()], goto)?;
+        let mut fingerprints = Fingerprints::load(&self.outdir);
+        if symtab_out.exists() {
+            let input_hash = hash_file(symtab_out)?;
+            if !fingerprints.is_up_to_date(goto, &[input_hash]) {
+                debug!(?symtab_out, "build link");
+                link_goto_binaries(&[symtab_out.to_path_buf()], goto)?;
+                fingerprints.record(goto, vec![input_hash])?;
+            }
+            fingerprints.save(&self.outdir)?;
         }
 
         // Create the project with the artifacts built by the compiler.
@@ -221,6 +300,7 @@ impl<'a> StandaloneProjectBuilder<'a> {
                 .into_values()
                 .filter(|artifact| artifact.path.exists() || dry_run)
                 .collect(),
+            fingerprints,
         })
     }
 
@@ -6,6 +6,9 @@ use std::ffi::OsStr;
 
 #[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
 pub enum ArtifactType {
+    /// A standalone Rust `#[test]` generated from a failing proof's CBMC counterexample, which
+    /// deterministically replays the exact failing inputs outside the model checker.
+    ConcretePlayback,
     Goto,
     Metadata,
     SymTab,
@@ -17,6 +20,7 @@ pub enum ArtifactType {
 impl ArtifactType {
     const fn extension(&self) -> &'static str {
         match self {
+            ArtifactType::ConcretePlayback => "playback.rs",
             ArtifactType::Goto => "out",
             ArtifactType::Metadata => "kani-metadata.json",
             ArtifactType::SymTab => "symtab.json",
@@ -5,6 +5,7 @@
 use std::ffi::OsStr;
 
 pub enum KaniFileType {
+    ConcretePlayback,
     Goto,
     Metadata,
     SymTab,
@@ -15,6 +16,7 @@ pub enum KaniFileType {
 impl KaniFileType {
     const fn extension(&self) -> &'static str {
         match self {
+            KaniFileType::ConcretePlayback => "playback.rs",
             KaniFileType::Goto => "symtab.out",
             KaniFileType::Metadata => "kani-metadata.json",
             KaniFileType::SymTab => "symtab.json",
@@ -94,6 +94,55 @@ macro_rules! generate_models {
                     Some(adjusted_size)
                 }
             }
+
+            /// Compute the byte offset that `core::intrinsics::arith_offset`/`<*T>::offset` would
+            /// add to a pointer, i.e. `count * elem_size`.
+            ///
+            /// `elem_size` is the already-validated, concrete size of the pointee. `count` may be
+            /// symbolic. This returns `None` when `count * elem_size` overflows `isize`, or when
+            /// the resulting offset is larger than `isize::MAX`, mirroring the checks the real
+            /// intrinsic's safety contract requires the caller to uphold.
+            #[kanitool::fn_marker = "PtrOffsetModel"]
+            pub(crate) fn ptr_offset(count: isize, elem_size: usize) -> Option<isize> {
+                let (offset, mul_overflow) = count.overflowing_mul(elem_size as isize);
+                if mul_overflow || offset.unsigned_abs() > isize::MAX as usize {
+                    None
+                } else {
+                    Some(offset)
+                }
+            }
+
+            /// Compute the pointee count that `<*T>::offset_from` would report between two
+            /// pointers into the same allocation, given their addresses and the pointee's size.
+            ///
+            /// Returns `None` when the byte distance between the two addresses isn't an exact
+            /// multiple of `elem_size`, which is what makes `offset_from` undefined behavior to
+            /// call on pointers that aren't both derived from the same object with the same
+            /// stride between them.
+            #[kanitool::fn_marker = "PtrOffsetFromModel"]
+            pub(crate) fn ptr_offset_from(
+                start_addr: usize,
+                end_addr: usize,
+                elem_size: usize,
+            ) -> Option<isize> {
+                let byte_offset = end_addr as isize - start_addr as isize;
+                if elem_size == 0 || byte_offset % (elem_size as isize) != 0 {
+                    None
+                } else {
+                    Some(byte_offset / elem_size as isize)
+                }
+            }
+
+            /// Compute the total byte size that `core::ptr::copy`/`copy_nonoverlapping` or
+            /// `write_bytes` would operate on, i.e. `count * elem_size`.
+            ///
+            /// Returns `None` when that product overflows `isize`, which is exactly the
+            /// precondition these intrinsics require callers to uphold.
+            #[kanitool::fn_marker = "SizeOfCopyModel"]
+            pub(crate) fn size_of_copy(count: usize, elem_size: usize) -> Option<usize> {
+                let (size, mul_overflow) = count.overflowing_mul(elem_size);
+                if mul_overflow || size > isize::MAX as usize { None } else { Some(size) }
+            }
         }
     };
 }
@@ -0,0 +1,90 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Bounded model of `std::collections::BTreeMap`.
+
+/// A fixed-capacity, kept-sorted association array standing in for `BTreeMap<K, V>`. Entries
+/// `0..len` are always maintained in ascending key order, so `first`/`last`/range-style
+/// consumers can rely on the same ordering guarantee the real B-tree gives, without this model
+/// needing to reproduce its node/rebalancing structure -- which a harness practically never
+/// reasons about directly.
+pub struct RmcBTreeMap<K, V, const CAP: usize> {
+    entries: [Option<(K, V)>; CAP],
+    len: usize,
+}
+
+impl<K: Ord, V, const CAP: usize> RmcBTreeMap<K, V, CAP> {
+    pub fn new() -> Self {
+        RmcBTreeMap { entries: [(); CAP].map(|_| None), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn position(&self, key: &K) -> Result<usize, usize> {
+        self.entries[..self.len]
+            .binary_search_by(|slot| slot.as_ref().expect("live slot").0.cmp(key))
+    }
+
+    /// Insert `key`/`value` in sorted position, returning the previous value if `key` was
+    /// already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        match self.position(&key) {
+            Ok(i) => self.entries[i].replace((key, value)).map(|(_, old)| old),
+            Err(i) => {
+                assert!(self.len < CAP, "RmcBTreeMap: insert exceeded its fixed capacity");
+                let mut to_insert = Some((key, value));
+                for slot in &mut self.entries[i..=self.len] {
+                    to_insert = std::mem::replace(slot, to_insert.take());
+                }
+                self.len += 1;
+                None
+            }
+        }
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.position(key).ok().and_then(|i| self.entries[i].as_ref()).map(|(_, v)| v)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.position(key).ok()?;
+        let removed = self.entries[i].take().map(|(_, v)| v);
+        for j in i..self.len - 1 {
+            self.entries[j] = self.entries[j + 1].take();
+        }
+        self.len -= 1;
+        removed
+    }
+
+    pub fn first_key_value(&self) -> Option<(&K, &V)> {
+        self.entries[0].as_ref().map(|(k, v)| (k, v))
+    }
+
+    pub fn last_key_value(&self) -> Option<(&K, &V)> {
+        if self.len == 0 {
+            return None;
+        }
+        self.entries[self.len - 1].as_ref().map(|(k, v)| (k, v))
+    }
+}
+
+/// Build an `RmcBTreeMap` from `key => value` pairs, analogous to `rmc_vec![...]`. The map's
+/// capacity is exactly the number of pairs given.
+#[macro_export]
+macro_rules! rmc_btreemap {
+    (@unit $key:expr => $value:expr) => {
+        ()
+    };
+    ($($key:expr => $value:expr),* $(,)?) => {{
+        const CAP: usize = [$($crate::rmc_btreemap!(@unit $key => $value)),*].len();
+        let mut map = $crate::collections::btree_map::RmcBTreeMap::<_, _, CAP>::new();
+        $(map.insert($key, $value);)*
+        map
+    }};
+}
@@ -0,0 +1,74 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Bounded model of `std::collections::HashMap`.
+
+/// A fixed-capacity association list standing in for `HashMap<K, V>`. Lookup/insert are O(CAP)
+/// linear scans rather than O(1) hashing: a real hash table's bucket layout depends on `K`'s
+/// `Hash` impl and the table's load factor, both of which would force a harness to reason about
+/// hash collisions it almost never actually cares about. Scanning a closed-form, fixed-size array
+/// instead gives the solver a loop-free (once `CAP` is fixed, fully unrolled at compile time)
+/// formula for "does this key already exist" with the same observable map semantics.
+pub struct RmcHashMap<K, V, const CAP: usize> {
+    entries: [Option<(K, V)>; CAP],
+    len: usize,
+}
+
+impl<K: PartialEq, V, const CAP: usize> RmcHashMap<K, V, CAP> {
+    pub fn new() -> Self {
+        RmcHashMap { entries: [(); CAP].map(|_| None), len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn position(&self, key: &K) -> Option<usize> {
+        self.entries.iter().position(|slot| matches!(slot, Some((k, _)) if k == key))
+    }
+
+    /// Insert `key`/`value`, returning the previous value if `key` was already present.
+    pub fn insert(&mut self, key: K, value: V) -> Option<V> {
+        if let Some(i) = self.position(&key) {
+            return self.entries[i].replace((key, value)).map(|(_, old)| old);
+        }
+        let slot = self.entries.iter().position(|slot| slot.is_none());
+        let i = slot.expect("RmcHashMap: insert exceeded its fixed capacity");
+        self.entries[i] = Some((key, value));
+        self.len += 1;
+        None
+    }
+
+    pub fn get(&self, key: &K) -> Option<&V> {
+        self.position(key).and_then(|i| self.entries[i].as_ref()).map(|(_, v)| v)
+    }
+
+    pub fn remove(&mut self, key: &K) -> Option<V> {
+        let i = self.position(key)?;
+        self.len -= 1;
+        self.entries[i].take().map(|(_, v)| v)
+    }
+
+    pub fn contains_key(&self, key: &K) -> bool {
+        self.position(key).is_some()
+    }
+}
+
+/// Build an `RmcHashMap` from `key => value` pairs, analogous to `rmc_vec![...]`. The map's
+/// capacity is exactly the number of pairs given.
+#[macro_export]
+macro_rules! rmc_hashmap {
+    (@unit $key:expr => $value:expr) => {
+        ()
+    };
+    ($($key:expr => $value:expr),* $(,)?) => {{
+        const CAP: usize = [$($crate::rmc_hashmap!(@unit $key => $value)),*].len();
+        let mut map = $crate::collections::hash_map::RmcHashMap::<_, _, CAP>::new();
+        $(map.insert($key, $value);)*
+        map
+    }};
+}
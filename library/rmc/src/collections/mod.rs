@@ -0,0 +1,19 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Verification-friendly models for standard collections, selected by `--use-abs --abs-type
+//! rmc` the same way `rmc_vec!`/the abstract `Vec` already are.
+//!
+//! Each model is backed by a fixed-capacity array rather than the real, heap-growing
+//! implementation, so its operations are either closed-form or bounded by a `const CAP` the
+//! caller picks -- no unwind bound is needed regardless of how large a harness's symbolic input
+//! is, only how large `CAP` is set to.
+//!
+//! `--abs-type c-ffi`, which lowers these same container operations to CBMC's existing C FFI
+//! shims instead of the pure-Rust models below, is selected and dispatched by the compiler/driver
+//! flag-handling layer, which (like the rest of that layer) isn't part of this source snapshot;
+//! this module only adds the pure-Rust side.
+
+pub mod btree_map;
+pub mod hash_map;
+pub mod vec_deque;
@@ -0,0 +1,88 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Bounded model of `std::collections::VecDeque`.
+
+/// A fixed-capacity ring buffer standing in for `VecDeque<T>`. `front` is the index of the
+/// oldest element; `len` (at most `CAP`) is how many of the `CAP` slots are live. Indexing wraps
+/// modulo `CAP`, so push/pop at either end are O(1) without shifting any other element -- the
+/// same cost profile a harness would see from the real `VecDeque`.
+pub struct RmcVecDeque<T, const CAP: usize> {
+    data: [Option<T>; CAP],
+    front: usize,
+    len: usize,
+}
+
+impl<T, const CAP: usize> RmcVecDeque<T, CAP> {
+    pub fn new() -> Self {
+        RmcVecDeque { data: [(); CAP].map(|_| None), front: 0, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    fn slot(&self, offset: usize) -> usize {
+        (self.front + offset) % CAP
+    }
+
+    pub fn push_back(&mut self, value: T) {
+        assert!(self.len < CAP, "RmcVecDeque: push_back exceeded its fixed capacity");
+        let slot = self.slot(self.len);
+        self.data[slot] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn push_front(&mut self, value: T) {
+        assert!(self.len < CAP, "RmcVecDeque: push_front exceeded its fixed capacity");
+        self.front = (self.front + CAP - 1) % CAP;
+        self.data[self.front] = Some(value);
+        self.len += 1;
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let value = self.data[self.front].take();
+        self.front = self.slot(1);
+        self.len -= 1;
+        value
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let slot = self.slot(self.len - 1);
+        self.len -= 1;
+        self.data[slot].take()
+    }
+
+    pub fn get(&self, index: usize) -> Option<&T> {
+        if index >= self.len {
+            return None;
+        }
+        self.data[self.slot(index)].as_ref()
+    }
+}
+
+/// Build an `RmcVecDeque` from a fixed-size literal, analogous to `rmc_vec![...]`. The deque's
+/// capacity is exactly the number of elements given, so later pushes beyond that require
+/// `RmcVecDeque::new` with an explicit larger `CAP` instead.
+#[macro_export]
+macro_rules! rmc_vecdeque {
+    (@unit $elem:expr) => {
+        ()
+    };
+    ($($elem:expr),* $(,)?) => {{
+        const CAP: usize = [$($crate::rmc_vecdeque!(@unit $elem)),*].len();
+        let mut deque = $crate::collections::vec_deque::RmcVecDeque::<_, CAP>::new();
+        $(deque.push_back($elem);)*
+        deque
+    }};
+}
@@ -0,0 +1,65 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+//! Helpers `ContractPass` and `LoopContractPass` splice in, in place of a contracted function
+//! call or loop: assert the precondition/invariant, havoc the affected state, then assume the
+//! postcondition/invariant. None of these are meant to be called directly by a harness.
+
+extern crate kani;
+
+/// Returns a fresh nondeterministic `T`, invalidating whatever a contracted function's return
+/// value or a `&mut` argument held before the call was replaced by its contract -- so a caller
+/// can't accidentally depend on the replaced function's actual behavior, only on what its
+/// `ensures` clause promises.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcContractHavoc"]
+pub fn havoc<T>() -> T {
+    kani::any()
+}
+
+/// Panics if `cond` is `false`. `ContractPass` calls this in place of a contracted function's
+/// `requires` clause, so a caller that doesn't uphold the precondition gets a verification
+/// failure at the call site, rather than one that would otherwise have shown up deep inside the
+/// (now-replaced) callee.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcContractAssertRequires"]
+pub fn assert_requires(cond: bool) {
+    assert!(cond, "function contract precondition violated");
+}
+
+/// Constrains further execution to paths where `cond` is `true`. `ContractPass` calls this with
+/// the contracted function's `ensures` clause evaluated against the havoced return value, so a
+/// caller can rely on the postcondition exactly as if the real function body had produced a
+/// return value that happened to satisfy it.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcContractAssumeEnsures"]
+pub fn assume_ensures(cond: bool) {
+    kani::assume(cond);
+}
+
+/// Panics if `cond` is `false`. `LoopContractPass` calls this with a loop's invariant, both at
+/// loop entry (the base case) and again after one more iteration from a havoced state (the step
+/// case).
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcLoopContractAssert"]
+pub fn assert_invariant(cond: bool) {
+    assert!(cond, "loop contract invariant violated");
+}
+
+/// Constrains further execution to paths where `cond` is `true`. `LoopContractPass` calls this
+/// with a loop's invariant right after havocing the loop's state, so the step and post checks
+/// only reason about states the invariant actually allows.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcLoopContractAssume"]
+pub fn assume_invariant(cond: bool) {
+    kani::assume(cond);
+}
+
+/// Panics unless `after < before`. `LoopContractPass` calls this at the end of the step case.
+/// No lower bound on the variant is checked, so with a signed variant a loop that never
+/// terminates can still pass this check on every iteration.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcLoopContractAssertDecreased"]
+pub fn assert_variant_decreased<T: PartialOrd>(before: T, after: T) {
+    assert!(after < before, "loop contract variant did not strictly decrease");
+}
@@ -2,13 +2,35 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 /// Stub signatures for std::mem methods.
+///
+/// These are real models, not no-ops: a harness that goes through `mem::swap`/`mem::replace`
+/// verifies against the actual memory effect, not a vacuous one. Like the real `core::mem`
+/// implementations, they're built on raw, non-dropping reads/writes so a value is never
+/// double-dropped or dropped early while it's being moved between the two locations.
 
 #[inline(never)]
 #[rustc_diagnostic_item = "RmcMemSwap"]
-pub fn swap<T>(_x: &mut T, _y: &mut T) {}
+pub fn swap<T>(x: &mut T, y: &mut T) {
+    // SAFETY: `x` and `y` are both valid, non-overlapping (they're distinct `&mut` borrows)
+    // places for a `T`. Reading one into a temporary before writing the other over it, and vice
+    // versa, exchanges their contents without ever dropping or duplicating either value.
+    unsafe {
+        let tmp: T = crate::ptr::read(x as *const T);
+        crate::ptr::write(x as *mut T, crate::ptr::read(y as *const T));
+        crate::ptr::write(y as *mut T, tmp);
+    }
+}
 
 #[inline(never)]
 #[rustc_diagnostic_item = "RmcMemReplace"]
-pub fn replace<T>(_dest: &mut T, _src: T) -> T {
-    unimplemented!("RMC mem::swap")
+pub fn replace<T>(dest: &mut T, src: T) -> T {
+    // SAFETY: `dest` is a valid place for a `T`. Reading it out first and writing `src` over it
+    // afterwards -- rather than writing first -- means the value we hand back to the caller is
+    // the old `*dest`, and the old value is never dropped in place (the caller owns it via the
+    // return value instead).
+    unsafe {
+        let old: T = crate::ptr::read(dest as *const T);
+        crate::ptr::write(dest as *mut T, src);
+        old
+    }
 }
@@ -0,0 +1,66 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+
+/// Stub signature for `core::slice::memchr::memchr`, the byte-search routine underlying
+/// `slice::contains`/`str::find` for a single byte.
+///
+/// The real implementation (and the one this model reproduces the observable behavior of) scans
+/// word-at-a-time with the classic SWAR "does this word contain a zero byte" test, rather than
+/// comparing one byte at a time -- which is what lets a harness search a symbolic-length buffer
+/// without unwinding once per byte.
+///
+/// The test for "does `usize` word `x` contain a zero byte" is
+/// `x.wrapping_sub(LO) & !x & HI != 0`, where `LO` is `0x0101..01` and `HI` is `0x8080..80`:
+/// subtracting 1 from each byte makes a zero byte borrow into its own high bit, and `& !x`
+/// suppresses the false positive a borrow produces in a byte that already had its top bit set
+/// (e.g. `0x81 - 1 = 0x80`, whose high bit is set with no borrow at all). To search for a
+/// specific byte `b` instead of a literal zero, broadcast it with `b as usize * LO` and XOR it
+/// into the word first: that turns every occurrence of `b` into a zero byte and leaves every
+/// other byte nonzero.
+#[inline(never)]
+#[rustc_diagnostic_item = "RmcMemchr"]
+pub fn memchr(needle: u8, haystack: &[u8]) -> Option<usize> {
+    const WORD: usize = core::mem::size_of::<usize>();
+    const LO: usize = usize::MAX / 255;
+    const HI: usize = LO << 7;
+
+    fn repeat_byte(b: u8) -> usize {
+        (b as usize).wrapping_mul(LO)
+    }
+
+    fn has_zero_byte(x: usize) -> bool {
+        x.wrapping_sub(LO) & !x & HI != 0
+    }
+
+    let repeated = repeat_byte(needle);
+    let len = haystack.len();
+    let mut i = 0;
+
+    while i + WORD <= len {
+        let chunk = usize::from_ne_bytes(haystack[i..i + WORD].try_into().unwrap());
+        if has_zero_byte(chunk ^ repeated) {
+            // The word-at-a-time test only tells us *some* byte in this word matches; fall back
+            // to a scalar scan over just these WORD bytes to report the first one, so the result
+            // is still the first occurrence rather than merely "a" occurrence.
+            return haystack[i..i + WORD].iter().position(|&b| b == needle).map(|off| i + off);
+        }
+        i += WORD;
+    }
+
+    // Fewer than WORD bytes remain. Pad them into a full word with a sentinel byte that can
+    // never equal `needle` (its bitwise complement), so the same SWAR test still cheaply rules
+    // out a match in the common case; when it doesn't, the scalar fallback is bounded to the real
+    // remaining bytes (`haystack[i..]`), so a "match" the padding itself would trigger is masked
+    // out and never reported.
+    if i < len {
+        let sentinel = !needle;
+        let mut tail = [sentinel; WORD];
+        tail[..len - i].copy_from_slice(&haystack[i..]);
+        let chunk = usize::from_ne_bytes(tail);
+        if has_zero_byte(chunk ^ repeated) {
+            return haystack[i..].iter().position(|&b| b == needle).map(|off| i + off);
+        }
+    }
+
+    None
+}
@@ -2,13 +2,35 @@
 // SPDX-License-Identifier: Apache-2.0 OR MIT
 
 /// Stub signatures for std::ptr methods.
+///
+/// Like `core::ptr::read`/`core::ptr::write`, these copy the bytes of a `T` into or out of a
+/// location without running (or skipping) any drop glue on the value that's conceptually *moved
+/// through* them -- the caller is responsible for not leaving two live owners of the same value
+/// behind, exactly as with the real intrinsics.
 
 #[inline(never)]
 #[rustc_diagnostic_item = "RmcPtrRead"]
-pub unsafe fn read<T>(_src: *const T) -> T {
-    unimplemented!("RMC ptr::read")
+pub unsafe fn read<T>(src: *const T) -> T {
+    // SAFETY: the caller upholds `core::ptr::read`'s contract: `src` is valid for reads and
+    // properly aligned. We bitwise-copy `*src` out as a `T` without running its destructor, so
+    // the original location still logically holds a (now aliased) value the caller must not also
+    // drop through `src`.
+    let mut dest = core::mem::MaybeUninit::<T>::uninit();
+    core::ptr::copy_nonoverlapping(src as *const u8, dest.as_mut_ptr() as *mut u8, core::mem::size_of::<T>());
+    dest.assume_init()
 }
 
 #[inline(never)]
 #[rustc_diagnostic_item = "RmcPtrWrite"]
-pub unsafe fn write<T>(_dst: *mut T, _src: T) {}
+pub unsafe fn write<T>(dst: *mut T, src: T) {
+    // SAFETY: the caller upholds `core::ptr::write`'s contract: `dst` is valid for writes and
+    // properly aligned. We bitwise-copy `src` into `*dst` without dropping whatever value was
+    // there before, exactly like the real intrinsic -- the caller is responsible for that old
+    // value, if it needed dropping at all.
+    core::ptr::copy_nonoverlapping(
+        &src as *const T as *const u8,
+        dst as *mut u8,
+        core::mem::size_of::<T>(),
+    );
+    core::mem::forget(src);
+}
@@ -0,0 +1,16 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// rmc-flags: --use-abs --abs-type rmc
+fn main() {
+    fn insert_get_test() {
+        let mut map = rmc_btreemap![3 => "c", 1 => "a", 2 => "b"];
+        assert!(map.first_key_value() == Some((&1, &"a")));
+        assert!(map.last_key_value() == Some((&3, &"c")));
+        assert!(map.get(&2) == Some(&"b"));
+        assert!(map.remove(&2) == Some("b"));
+        assert!(map.len() == 2);
+        assert!(map.last_key_value() == Some((&3, &"c")));
+    }
+
+    insert_get_test();
+}
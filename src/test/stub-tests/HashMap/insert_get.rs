@@ -0,0 +1,16 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// rmc-flags: --use-abs --abs-type rmc
+fn main() {
+    fn insert_get_test() {
+        let mut map = rmc_hashmap![1 => "a", 2 => "b"];
+        assert!(map.get(&1) == Some(&"a"));
+        assert!(map.insert(2, "c") == Some("b"));
+        assert!(map.get(&2) == Some(&"c"));
+        assert!(map.remove(&1) == Some("a"));
+        assert!(map.get(&1).is_none());
+        assert!(map.len() == 1);
+    }
+
+    insert_get_test();
+}
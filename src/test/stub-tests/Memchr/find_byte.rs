@@ -0,0 +1,22 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// rmc-flags: --use-abs --abs-type rmc
+fn main() {
+    fn find_byte_test() {
+        // `<[u8]>::contains` specializes to the `memchr` byte-search routine the `RmcMemchr`
+        // abstraction replaces, so this is the word-at-a-time path, not a plain scalar scan.
+        let haystack = [b'a', b'b', b'c', b'x', b'e', b'f', b'g', b'h', b'i', b'j'];
+        assert!(haystack.contains(&b'x'));
+        assert!(!haystack[..3].contains(&b'x'));
+
+        let absent = [b'a', b'b', b'c', b'd', b'e', b'f', b'g', b'h', b'i', b'j'];
+        assert!(!absent.contains(&b'x'));
+
+        // Fewer than a word's worth of bytes, exercising the sentinel-padded tail fallback.
+        let short = [b'a', b'x', b'c'];
+        assert!(short.contains(&b'x'));
+        assert!(!short[..1].contains(&b'x'));
+    }
+
+    find_byte_test();
+}
@@ -0,0 +1,18 @@
+// Copyright Amazon.com, Inc. or its affiliates. All Rights Reserved.
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// rmc-flags: --use-abs --abs-type rmc
+fn main() {
+    fn push_pop_test() {
+        let mut deque = rmc::collections::vec_deque::RmcVecDeque::<i32, 4>::new();
+        deque.push_back(1);
+        deque.push_back(2);
+        deque.push_back(3);
+        deque.push_back(4);
+        assert!(deque.len() == 4);
+        assert!(deque.pop_front() == Some(1));
+        assert!(deque.pop_back() == Some(4));
+        assert!(deque.len() == 2);
+    }
+
+    push_pop_test();
+}
@@ -0,0 +1,25 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// kani-flags: -Zfunction-contracts
+
+//! Exercise a contract at a *caller's* call site (mode 2 in `function_contracts`'s module doc),
+//! rather than proving the contracted function's own body satisfies it (that's what
+//! `#[kani::proof_for_contract]` in the other files here does). `half`'s call below is spliced
+//! into assert(requires)/havoc(return)/assume(ensures) by `ContractPass`, so this harness only
+//! ever sees values `half` is allowed to produce under its contract -- it never runs `half`'s
+//! actual division.
+
+#[kani::requires(x % 2 == 0)]
+#[kani::ensures(|result: &u32| *result * 2 == x)]
+fn half(x: u32) -> u32 {
+    x / 2
+}
+
+#[kani::proof]
+fn half_call_site() {
+    let x: u32 = kani::any();
+    kani::assume(x % 2 == 0 && x <= 1000);
+
+    let result = half(x);
+    assert!(result * 2 == x);
+}
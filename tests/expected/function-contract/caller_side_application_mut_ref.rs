@@ -0,0 +1,23 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// kani-flags: -Zfunction-contracts
+
+//! Same caller-side splice as `caller_side_application.rs`, but with a `&mut` contracted
+//! parameter: `requires`/`ensures` both read `*x` at the call site, and the havoc step
+//! immediately below them in `splice_contract` also reads `x` to find what to havoc. All three
+//! reads have to happen without consuming `x`'s place, since MIR only lets a `&mut` argument be
+//! moved out of its local once.
+
+#[kani::requires(*x < 1000)]
+#[kani::ensures(|_result: &()| true)]
+fn increment(x: &mut u32) {
+    *x += 1;
+}
+
+#[kani::proof]
+fn increment_call_site() {
+    let mut x: u32 = kani::any();
+    kani::assume(x < 1000);
+
+    increment(&mut x);
+}
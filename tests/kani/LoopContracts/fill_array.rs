@@ -0,0 +1,30 @@
+// Copyright Kani Contributors
+// SPDX-License-Identifier: Apache-2.0 OR MIT
+// kani-flags: -Z loop-contracts
+
+//! Check a `while` loop annotated with `#[kani::loop_invariant]`/`#[kani::loop_variant]`, with
+//! `n` left fully symbolic rather than bounded -- the entire point of `LoopContractPass` (see its
+//! module doc) is replacing unwinding with the base/step/post checks it splices in, so a harness
+//! that still needed `#[kani::unwind(N)]` wouldn't actually be exercising the contract.
+//!
+//! Like `function_contracts::ContractSpec`, `LoopContractSpec` is built by the attribute-
+//! processing layer that resolves `#[kani::loop_invariant]`/`#[kani::loop_variant]` into
+//! `mir_transform::set_loop_contracts`'s input -- not part of this pass, and not part of this
+//! trimmed checkout either, the same way contract attribute processing isn't.
+//! `mir_transform::run_transformation_passes` already runs `LoopContractPass` unconditionally
+//! alongside `ContractPass` once contracts are set, so this harness carries the same level of
+//! confidence as `tests/expected/function-contract`'s.
+
+#[kani::proof]
+fn fill_array() {
+    let n: usize = kani::any();
+
+    let mut filled = 0usize;
+    #[kani::loop_invariant(|i: usize| i <= n)]
+    #[kani::loop_variant(|i: usize| n - i)]
+    while filled < n {
+        filled += 1;
+    }
+
+    assert!(filled == n);
+}
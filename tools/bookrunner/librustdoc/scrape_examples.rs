@@ -3,14 +3,28 @@
 // Modifications Copyright Kani Contributors
 // See GitHub history for details.
 //! This module analyzes crates to find call sites that can serve as examples in the documentation.
+//! It also doubles as a harness generator: once we know where a function is called and with what
+//! argument types, we can seed a `#[kani::proof]` skeleton from that usage instead of requiring the
+//! user to write one from scratch.
+//!
+//! `encode_call_locations`/`CallLocationIndex` are the two ends of this module's sidecar pipeline
+//! (written once per crate's compilation, merged later by whatever drives a full workspace scrape);
+//! that driver lives outside this trimmed checkout, so neither has a caller visible in this tree.
 
-use rustc_data_structures::fx::FxHashMap;
+use rustc_data_structures::fx::{FxHashMap, FxHashSet};
 use rustc_hir::intravisit::{self, Visitor};
+use rustc_hir::{Expr, ExprKind, HirId};
 use rustc_macros::{Decodable, Encodable};
+use rustc_middle::hir::map::Map;
 use rustc_middle::ty::TyCtxt;
-use rustc_span::{def_id::DefPathHash, edition::Edition, BytePos, FileName, SourceFile};
+use rustc_serialize::{opaque, Decodable as _, Encodable as _};
+use rustc_span::{def_id::DefId, def_id::DefPathHash, edition::Edition, BytePos, FileName, SourceFile};
 
-use std::path::PathBuf;
+use std::collections::hash_map::Entry;
+use std::fmt::Write as _;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
 
 #[derive(Encodable, Decodable, Debug, Clone)]
 crate struct SyntaxRange {
@@ -18,10 +32,43 @@ crate struct SyntaxRange {
     crate line_span: (usize, usize),
 }
 
+/// The concrete argument seen at one call site, resolved via the enclosing body's `LocalDecls`.
+#[derive(Encodable, Decodable, Debug, Clone)]
+crate struct ArgumentData {
+    /// The inferred type of the argument, pretty-printed.
+    crate ty: String,
+    /// Location of the argument expression itself.
+    crate span: SyntaxRange,
+}
+
 #[derive(Encodable, Decodable, Debug, Clone)]
 crate struct CallLocation {
     crate call_expr: SyntaxRange,
     crate enclosing_item: SyntaxRange,
+    /// The concrete argument types observed at this call site, in argument order.
+    crate arguments: Vec<ArgumentData>,
+}
+
+impl CallLocation {
+    crate fn new(
+        call_expr_span: rustc_span::Span,
+        enclosing_item_span: rustc_span::Span,
+        source_file: &SourceFile,
+        arguments: Vec<ArgumentData>,
+    ) -> Self {
+        let enclosing_item = SyntaxRange::new(enclosing_item_span, source_file);
+        let call_expr = SyntaxRange::new(call_expr_span, source_file);
+        CallLocation { call_expr, enclosing_item, arguments }
+    }
+}
+
+impl SyntaxRange {
+    crate fn new(span: rustc_span::Span, file: &SourceFile) -> Self {
+        let get_pos = |bytepos: BytePos| file.original_relative_byte_pos(bytepos).0;
+        let byte_span = (get_pos(span.lo()), get_pos(span.hi()));
+        let line_span = (file.lookup_line(span.lo()).unwrap(), file.lookup_line(span.hi()).unwrap());
+        SyntaxRange { byte_span, line_span }
+    }
 }
 
 #[derive(Encodable, Decodable, Debug, Clone)]
@@ -30,7 +77,297 @@ crate struct CallData {
     crate url: String,
     crate display_name: String,
     crate edition: Edition,
+    /// Whether the first call recorded for this file targeted `target_def_id` through method-call
+    /// syntax (`receiver.method(..)`) rather than a plain function call; later calls in the same
+    /// file do not change it. HIR records the receiver as
+    /// `arguments[0]` either way, but a method can only be invoked back as `receiver.method(rest)`,
+    /// never as a plain function call on a bare method name -- `generate_harness_skeleton` needs
+    /// this to emit syntax that actually compiles.
+    crate is_method_call: bool,
 }
 
 crate type FnCallLocations = FxHashMap<PathBuf, CallData>;
 crate type AllCallLocations = FxHashMap<DefPathHash, FnCallLocations>;
+
+/// Visitor that locates every HIR call expression targeting `target_def_id` within a body, and
+/// records the concrete argument types seen at each one.
+crate struct FindCalls<'a, 'tcx> {
+    crate tcx: TyCtxt<'tcx>,
+    crate target_def_id: DefId,
+    crate calls: &'a mut FnCallLocations,
+    crate url: String,
+    crate display_name: String,
+    crate edition: Edition,
+}
+
+impl<'a, 'tcx> Visitor<'tcx> for FindCalls<'a, 'tcx> {
+    type Map = Map<'tcx>;
+    type NestedFilter = rustc_middle::hir::nested_filter::All;
+
+    fn nested_visit_map(&mut self) -> Self::Map {
+        self.tcx.hir()
+    }
+
+    fn visit_expr(&mut self, ex: &'tcx Expr<'tcx>) {
+        intravisit::walk_expr(self, ex);
+
+        let tcx = self.tcx;
+        let hir = tcx.hir();
+
+        let (fn_expr, args, is_method_call) = match &ex.kind {
+            ExprKind::Call(fn_expr, args) => (*fn_expr, *args, false),
+            ExprKind::MethodCall(_, args, _) => (ex, *args, true),
+            _ => return,
+        };
+
+        let Some(callee_def_id) = resolve_callee(tcx, ex, fn_expr) else { return };
+        if callee_def_id != self.target_def_id {
+            return;
+        }
+
+        let enclosing_owner_id = hir.enclosing_body_owner(ex.hir_id);
+        let typeck = tcx.typeck(enclosing_owner_id);
+        let arguments = args
+            .iter()
+            .map(|arg| ArgumentData {
+                ty: typeck.expr_ty_adjusted(arg).to_string(),
+                span: SyntaxRange::new(arg.span, source_file(tcx, ex.hir_id)),
+            })
+            .collect();
+
+        let enclosing_item_span =
+            tcx.hir().span_with_body(hir.get_parent_item(ex.hir_id).into());
+        let source_file = source_file(tcx, ex.hir_id);
+        let location =
+            CallLocation::new(ex.span, enclosing_item_span, source_file, arguments);
+
+        self.calls
+            .entry(PathBuf::from(source_file.name.prefer_remapped().to_string()))
+            .or_insert_with(|| CallData {
+                locations: vec![],
+                url: self.url.clone(),
+                display_name: self.display_name.clone(),
+                edition: self.edition,
+                is_method_call,
+            })
+            .locations
+            .push(location);
+    }
+}
+
+fn source_file<'tcx>(tcx: TyCtxt<'tcx>, hir_id: HirId) -> &'tcx SourceFile {
+    let span = tcx.hir().span(hir_id);
+    tcx.sess.source_map().lookup_source_file(span.lo())
+}
+
+/// Best-effort resolution of the `DefId` a call expression targets, covering both direct function
+/// calls and method calls.
+fn resolve_callee<'tcx>(tcx: TyCtxt<'tcx>, ex: &Expr<'tcx>, fn_expr: &Expr<'tcx>) -> Option<DefId> {
+    let enclosing_owner_id = tcx.hir().enclosing_body_owner(ex.hir_id);
+    let typeck = tcx.typeck(enclosing_owner_id);
+    if let ExprKind::MethodCall(..) = ex.kind {
+        typeck.type_dependent_def_id(ex.hir_id)
+    } else if let ExprKind::Path(qpath) = &fn_expr.kind {
+        typeck.qpath_res(qpath, fn_expr.hir_id).opt_def_id()
+    } else {
+        None
+    }
+}
+
+/// Generate a Kani harness skeleton for `target`, seeded from the concrete argument types observed
+/// across every recorded call site in `locations`. Returns `None` if we never saw a call.
+///
+/// The generated harness declares a `kani::any()` of the inferred type for each parameter (using
+/// the most common concrete type observed at that position) and calls `target` with them. Since
+/// `FnCallLocations` is `Encodable`, the caller can persist the locations this skeleton was derived
+/// from and diff them between runs to detect when usage has changed enough to warrant regenerating
+/// the harness.
+///
+/// `target_name` is always the bare function/method name, never a UFCS path: if the first
+/// `CallData` in `locations` has `is_method_call` set (the others are not consulted), HIR's
+/// `arguments[0]` is
+/// the receiver, and `target_name` can only ever be invoked back as `arg0.target_name(rest..)` --
+/// `{target_name}(arg0, rest..)` is not valid syntax for a method and would fail to compile.
+crate fn generate_harness_skeleton(
+    target_name: &str,
+    harness_name: &str,
+    locations: &FnCallLocations,
+) -> Option<String> {
+    let arg_types = most_common_argument_types(locations)?;
+    let is_method_call = locations.values().next()?.is_method_call;
+
+    let mut harness = String::new();
+    writeln!(harness, "#[kani::proof]").unwrap();
+    writeln!(harness, "pub fn {harness_name}() {{").unwrap();
+    let mut arg_names = Vec::with_capacity(arg_types.len());
+    for (i, ty) in arg_types.iter().enumerate() {
+        let arg_name = format!("arg{i}");
+        writeln!(harness, "    let {arg_name}: {ty} = kani::any();").unwrap();
+        arg_names.push(arg_name);
+    }
+    if is_method_call {
+        let receiver = arg_names.first().expect("method call must have a receiver argument");
+        writeln!(harness, "    {receiver}.{target_name}({});", arg_names[1..].join(", ")).unwrap();
+    } else {
+        writeln!(harness, "    {target_name}({});", arg_names.join(", ")).unwrap();
+    }
+    writeln!(harness, "}}").unwrap();
+    Some(harness)
+}
+
+/// For each argument position, pick the most frequently observed concrete type across all
+/// recorded call sites. Returns `None` if no call site was recorded.
+fn most_common_argument_types(locations: &FnCallLocations) -> Option<Vec<String>> {
+    let mut counts: Vec<FxHashMap<&str, usize>> = vec![];
+    for call_data in locations.values() {
+        for location in &call_data.locations {
+            for (i, arg) in location.arguments.iter().enumerate() {
+                if counts.len() <= i {
+                    counts.resize_with(i + 1, FxHashMap::default);
+                }
+                *counts[i].entry(arg.ty.as_str()).or_insert(0) += 1;
+            }
+        }
+    }
+    if locations.values().all(|call_data| call_data.locations.is_empty()) {
+        return None;
+    }
+    Some(
+        counts
+            .iter()
+            .map(|by_ty| by_ty.iter().max_by_key(|(_, count)| **count).unwrap().0.to_string())
+            .collect(),
+    )
+}
+
+/// Write this crate's `AllCallLocations` to a sidecar file next to its regular output, so that
+/// other crates in the workspace (or a later tool invocation) can pick it back up and merge it
+/// into the cross-crate index.
+crate fn encode_call_locations(calls: &AllCallLocations, path: &Path) -> io::Result<()> {
+    let mut encoder = opaque::FileEncoder::new(path)?;
+    calls.encode(&mut encoder).unwrap();
+    encoder.flush()?;
+    Ok(())
+}
+
+/// Read back a sidecar file produced by [`encode_call_locations`].
+crate fn decode_call_locations(path: &Path) -> io::Result<AllCallLocations> {
+    let bytes = fs::read(path)?;
+    let mut decoder = opaque::Decoder::new(&bytes, 0);
+    Ok(AllCallLocations::decode(&mut decoder))
+}
+
+/// Merge `from` into `into`, deduplicating `CallLocation`s that point at the same `(file,
+/// byte_span)` (the same call site recorded twice, e.g. because two crates happened to scan the
+/// same file).
+fn merge_fn_call_locations(into: &mut FnCallLocations, from: FnCallLocations) {
+    for (file, call_data) in from {
+        match into.entry(file) {
+            Entry::Vacant(entry) => {
+                entry.insert(call_data);
+            }
+            Entry::Occupied(mut entry) => {
+                let existing = entry.get_mut();
+                let mut seen: FxHashSet<(u32, u32)> =
+                    existing.locations.iter().map(|loc| loc.call_expr.byte_span).collect();
+                existing
+                    .locations
+                    .extend(call_data.locations.into_iter().filter(|loc| seen.insert(loc.call_expr.byte_span)));
+            }
+        }
+    }
+}
+
+/// Union per-crate `AllCallLocations` maps into a single workspace-wide index. Because
+/// `DefPathHash` is stable across crates and recompiles, entries for a function defined in crate
+/// A but called from crates B and C all collapse into one record.
+crate fn merge_all_call_locations(maps: impl IntoIterator<Item = AllCallLocations>) -> AllCallLocations {
+    let mut merged: AllCallLocations = FxHashMap::default();
+    for map in maps {
+        for (def_path_hash, fn_calls) in map {
+            match merged.entry(def_path_hash) {
+                Entry::Vacant(entry) => {
+                    entry.insert(fn_calls);
+                }
+                Entry::Occupied(mut entry) => merge_fn_call_locations(entry.get_mut(), fn_calls),
+            }
+        }
+    }
+    merged
+}
+
+/// A workspace-wide, mergeable call-location database built by unioning the sidecar files
+/// produced by every crate's compilation (see [`encode_call_locations`]).
+crate struct CallLocationIndex {
+    all_calls: AllCallLocations,
+}
+
+impl CallLocationIndex {
+    /// Load and merge the sidecar files at `paths`, keeping entries for the same `DefPathHash`
+    /// together regardless of which crate produced them.
+    crate fn load(paths: &[PathBuf]) -> io::Result<Self> {
+        let maps =
+            paths.iter().map(|path| decode_call_locations(path)).collect::<io::Result<Vec<_>>>()?;
+        Ok(CallLocationIndex { all_calls: merge_all_call_locations(maps) })
+    }
+
+    /// Answer "where is this function used anywhere in the workspace" for downstream tooling such
+    /// as harness generation or coverage mapping.
+    crate fn locations_for(&self, target: DefPathHash) -> Option<&FnCallLocations> {
+        self.all_calls.get(&target)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn call_data(byte_spans: &[(u32, u32)]) -> CallData {
+        CallData {
+            locations: byte_spans
+                .iter()
+                .map(|&call_expr_byte_span| CallLocation {
+                    call_expr: SyntaxRange { byte_span: call_expr_byte_span, line_span: (0, 0) },
+                    enclosing_item: SyntaxRange { byte_span: (0, 0), line_span: (0, 0) },
+                    arguments: vec![],
+                })
+                .collect(),
+            url: String::from("https://example.com"),
+            display_name: String::from("example"),
+            edition: Edition::Edition2021,
+            is_method_call: false,
+        }
+    }
+
+    #[test]
+    fn merge_fn_call_locations_dedups_same_byte_span() {
+        let mut into: FnCallLocations = FxHashMap::default();
+        into.insert(PathBuf::from("a.rs"), call_data(&[(0, 10)]));
+
+        let mut from: FnCallLocations = FxHashMap::default();
+        // Same call site recorded again (e.g. two crates scanning the same file) plus one new one.
+        from.insert(PathBuf::from("a.rs"), call_data(&[(0, 10), (20, 30)]));
+
+        merge_fn_call_locations(&mut into, from);
+
+        let locations = &into.get(&PathBuf::from("a.rs")).unwrap().locations;
+        assert_eq!(locations.len(), 2);
+        let spans: FxHashSet<_> = locations.iter().map(|loc| loc.call_expr.byte_span).collect();
+        assert_eq!(spans, FxHashSet::from_iter([(0, 10), (20, 30)]));
+    }
+
+    #[test]
+    fn merge_fn_call_locations_keeps_distinct_files_separate() {
+        let mut into: FnCallLocations = FxHashMap::default();
+        into.insert(PathBuf::from("a.rs"), call_data(&[(0, 10)]));
+
+        let mut from: FnCallLocations = FxHashMap::default();
+        from.insert(PathBuf::from("b.rs"), call_data(&[(0, 10)]));
+
+        merge_fn_call_locations(&mut into, from);
+
+        assert_eq!(into.len(), 2);
+        assert_eq!(into[&PathBuf::from("a.rs")].locations.len(), 1);
+        assert_eq!(into[&PathBuf::from("b.rs")].locations.len(), 1);
+    }
+}
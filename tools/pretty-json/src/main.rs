@@ -1,33 +1,625 @@
 // Copyright Kani Contributors
 // SPDX-License-Identifier: Apache-2.0 OR MIT
-//! Little script that given a input json file, prints a pretty-json to the standard output.
-//! Usage: pretty-json [INPUT_JSON_FILE]
-//!    or: cargo run -p pretty-json -- [INPUT_JSON_FILE]
+//! Little script that given a input json file, prints it back out in one of a few selectable
+//! ways. Usage: pretty-json [INPUT_JSON_FILE] [--terse | --filter KEY]
+//!    or: cargo run -p pretty-json -- [INPUT_JSON_FILE] [--terse | --filter KEY]
+//!
+//! The default and `--filter` modes read and re-emit the file through a small streaming
+//! tokenizer rather than `serde_json::from_reader::<Value>`, so peak memory stays O(nesting
+//! depth) instead of O(file size) -- the original whole-file `Value` approach falls over on the
+//! multi-gigabyte property/trace dumps CBMC can produce. `--terse` still parses the whole file
+//! into a `Value`, because deciding whether a container is collapsible requires already knowing
+//! its entire contents; that mode trades the memory win back for a more readable summary view.
 use std::{
+    env,
     fs::File,
-    io::{BufReader, Result},
+    io::{self, BufReader, BufWriter, Bytes, Read, Write},
+    iter::Peekable,
 };
 
 fn error(msg: &str) -> ! {
     eprintln!("Error: {msg}");
     eprintln!(
-        "Usage: pretty-json [INPUT_JSON_FILE] \n   \
-        or: cargo run -p pretty-json -- [INPUT_JSON_FILE]"
+        "Usage: pretty-json [INPUT_JSON_FILE] [--terse | --filter KEY] \n   \
+        or: cargo run -p pretty-json -- [INPUT_JSON_FILE] [--terse | --filter KEY]"
     );
     std::process::exit(1)
 }
 
+/// The output mode selected on the command line.
+enum Mode {
+    /// Fully expanded pretty-printing: the original behavior, one scalar per line.
+    Pretty,
+    /// Like `Pretty`, but an array/object whose direct children are all scalars collapses onto a
+    /// single line instead of one line per child.
+    Terse,
+    /// Only emit the value of the named top-level key (e.g. `"result"` from CBMC's JSON output),
+    /// pretty-printed; every other top-level key is skipped without being buffered.
+    Filter(String),
+}
+
 fn main() {
-    let mut args = std::env::args();
-    let filename = args.nth(1).unwrap_or_else(|| error("No argument provided"));
-    pretty_json(&filename).unwrap_or_else(|err| error(&err.to_string()))
+    let args: Vec<String> = env::args().collect();
+    let (filename, mode) = parse_args(&args).unwrap_or_else(|msg| error(&msg));
+    run(&filename, &mode).unwrap_or_else(|err| error(&err.to_string()))
+}
+
+fn parse_args(args: &[String]) -> Result<(String, Mode), String> {
+    let mut filename = None;
+    let mut mode = Mode::Pretty;
+    let mut iter = args.iter().skip(1);
+    while let Some(arg) = iter.next() {
+        match arg.as_str() {
+            "--terse" => mode = Mode::Terse,
+            "--filter" => {
+                let key = iter.next().ok_or("--filter requires a key name")?;
+                mode = Mode::Filter(key.clone());
+            }
+            _ if filename.is_none() => filename = Some(arg.clone()),
+            other => return Err(format!("Unexpected argument `{other}`")),
+        }
+    }
+    filename.ok_or_else(|| "No argument provided".to_string()).map(|f| (f, mode))
+}
+
+fn run(filename: &str, mode: &Mode) -> io::Result<()> {
+    eprintln!("Parsing {filename}");
+    let mut out = BufWriter::new(io::stdout());
+    match mode {
+        Mode::Pretty => {
+            let mut lexer = Lexer::new(BufReader::new(File::open(filename)?));
+            print_pretty(&mut lexer, &mut out)?;
+        }
+        Mode::Filter(key) => {
+            let mut lexer = Lexer::new(BufReader::new(File::open(filename)?));
+            print_filter(&mut lexer, &mut out, key)?;
+        }
+        Mode::Terse => {
+            let value: serde_json::Value =
+                serde_json::from_reader(BufReader::new(File::open(filename)?))?;
+            write_terse(&mut out, &value, 0)?;
+            writeln!(out)?;
+        }
+    }
+    out.flush()
+}
+
+// ---- Streaming tokenizer, used by `Pretty` and `Filter` ----------------------------------------
+
+/// One JSON atom. Structural commas and colons are swallowed by the lexer itself: a caller only
+/// needs to know where a value starts and ends, not how the source text separated them.
+#[derive(Debug, Clone)]
+enum Token {
+    ObjectStart,
+    ObjectEnd,
+    ArrayStart,
+    ArrayEnd,
+    String(String),
+    Number(String),
+    Bool(bool),
+    Null,
+}
+
+/// A minimal JSON lexer that never buffers more than the current scalar token, so peak memory is
+/// O(depth) rather than O(file size).
+struct Lexer<R: Read> {
+    bytes: Peekable<Bytes<R>>,
+}
+
+impl<R: Read> Lexer<R> {
+    fn new(reader: R) -> Self {
+        Lexer { bytes: reader.bytes().peekable() }
+    }
+
+    fn peek_byte(&mut self) -> io::Result<Option<u8>> {
+        match self.bytes.peek() {
+            Some(Ok(b)) => Ok(Some(*b)),
+            Some(Err(_)) => Err(self.bytes.next().unwrap().unwrap_err()),
+            None => Ok(None),
+        }
+    }
+
+    fn next_byte(&mut self) -> io::Result<Option<u8>> {
+        self.bytes.next().transpose()
+    }
+
+    fn skip_whitespace(&mut self) -> io::Result<()> {
+        while let Some(b) = self.peek_byte()? {
+            if matches!(b, b' ' | b'\t' | b'\n' | b'\r') {
+                self.next_byte()?;
+            } else {
+                break;
+            }
+        }
+        Ok(())
+    }
+
+    /// Read the next token, or `None` at end of input.
+    fn next_token(&mut self) -> io::Result<Option<Token>> {
+        loop {
+            self.skip_whitespace()?;
+            let b = match self.peek_byte()? {
+                Some(b) => b,
+                None => return Ok(None),
+            };
+            return Ok(Some(match b {
+                b'{' => {
+                    self.next_byte()?;
+                    Token::ObjectStart
+                }
+                b'}' => {
+                    self.next_byte()?;
+                    Token::ObjectEnd
+                }
+                b'[' => {
+                    self.next_byte()?;
+                    Token::ArrayStart
+                }
+                b']' => {
+                    self.next_byte()?;
+                    Token::ArrayEnd
+                }
+                b',' | b':' => {
+                    self.next_byte()?;
+                    continue;
+                }
+                b'"' => Token::String(self.read_string()?),
+                b't' => {
+                    self.expect_literal("true")?;
+                    Token::Bool(true)
+                }
+                b'f' => {
+                    self.expect_literal("false")?;
+                    Token::Bool(false)
+                }
+                b'n' => {
+                    self.expect_literal("null")?;
+                    Token::Null
+                }
+                _ => Token::Number(self.read_number()?),
+            }));
+        }
+    }
+
+    fn expect_literal(&mut self, lit: &str) -> io::Result<()> {
+        for expected in lit.bytes() {
+            match self.next_byte()? {
+                Some(b) if b == expected => {}
+                _ => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::InvalidData,
+                        format!("expected literal `{lit}`"),
+                    ))
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn read_string(&mut self) -> io::Result<String> {
+        self.next_byte()?; // opening quote
+        let mut buf = Vec::new();
+        loop {
+            let b = self
+                .next_byte()?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated string"))?;
+            match b {
+                b'"' => {
+                    return String::from_utf8(buf)
+                        .map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+                }
+                b'\\' => self.read_escape(&mut buf)?,
+                other => buf.push(other),
+            }
+        }
+    }
+
+    /// Read one escape sequence, assuming the lexer is positioned right after its leading `\`,
+    /// and append its decoded bytes to `buf`.
+    fn read_escape(&mut self, buf: &mut Vec<u8>) -> io::Result<()> {
+        let escaped = self
+            .next_byte()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated escape"))?;
+        match escaped {
+            b'"' => buf.push(b'"'),
+            b'\\' => buf.push(b'\\'),
+            b'/' => buf.push(b'/'),
+            b'n' => buf.push(b'\n'),
+            b't' => buf.push(b'\t'),
+            b'r' => buf.push(b'\r'),
+            b'b' => buf.push(0x08),
+            b'f' => buf.push(0x0c),
+            b'u' => self.read_unicode_escape(buf)?,
+            other => buf.push(other),
+        }
+        Ok(())
+    }
+
+    /// Read a `\uXXXX` escape's 4 hex digits and append the resulting scalar's UTF-8 bytes to
+    /// `buf`. A high surrogate (`\uD800`-`\uDBFF`) only encodes a full code point together with
+    /// an immediately-following low surrogate (`\uDC00`-`\uDFFF`) -- the pair JSON uses to
+    /// represent any character outside the Basic Multilingual Plane, like most emoji and several
+    /// CJK extension blocks -- so this looks ahead for that pairing instead of decoding each `\u`
+    /// escape in isolation, which would turn one such character into two garbage replacement
+    /// characters.
+    fn read_unicode_escape(&mut self, buf: &mut Vec<u8>) -> io::Result<()> {
+        let high = self.read_hex4()?;
+        if !(0xD800..=0xDBFF).contains(&high) {
+            push_char(buf, char::from_u32(high).unwrap_or(char::REPLACEMENT_CHARACTER));
+            return Ok(());
+        }
+        if self.peek_byte()? != Some(b'\\') {
+            push_char(buf, char::REPLACEMENT_CHARACTER);
+            return Ok(());
+        }
+        self.next_byte()?; // consume the backslash
+        match self.next_byte()? {
+            Some(b'u') => {
+                let low = self.read_hex4()?;
+                if (0xDC00..=0xDFFF).contains(&low) {
+                    let combined = 0x10000 + ((high - 0xD800) << 10) + (low - 0xDC00);
+                    push_char(buf, char::from_u32(combined).unwrap_or(char::REPLACEMENT_CHARACTER));
+                } else {
+                    // `low` wasn't actually a low surrogate, so the high surrogate was lone; it
+                    // stands on its own, and so does whatever `low` decodes to.
+                    push_char(buf, char::REPLACEMENT_CHARACTER);
+                    push_char(buf, char::from_u32(low).unwrap_or(char::REPLACEMENT_CHARACTER));
+                }
+            }
+            Some(other) => {
+                // The backslash started an unrelated escape, not a second `\u`; the high
+                // surrogate was lone, and that other escape still needs to be applied.
+                push_char(buf, char::REPLACEMENT_CHARACTER);
+                self.apply_escape_byte(other, buf)?;
+            }
+            None => return Err(io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated escape")),
+        }
+        Ok(())
+    }
+
+    /// The non-lookahead part of [`read_escape`](Self::read_escape), factored out so
+    /// [`read_unicode_escape`](Self::read_unicode_escape) can apply an escape byte it already
+    /// consumed while checking for a low surrogate.
+    fn apply_escape_byte(&mut self, escaped: u8, buf: &mut Vec<u8>) -> io::Result<()> {
+        match escaped {
+            b'"' => buf.push(b'"'),
+            b'\\' => buf.push(b'\\'),
+            b'/' => buf.push(b'/'),
+            b'n' => buf.push(b'\n'),
+            b't' => buf.push(b'\t'),
+            b'r' => buf.push(b'\r'),
+            b'b' => buf.push(0x08),
+            b'f' => buf.push(0x0c),
+            b'u' => self.read_unicode_escape(buf)?,
+            other => buf.push(other),
+        }
+        Ok(())
+    }
+
+    fn read_hex4(&mut self) -> io::Result<u32> {
+        let mut hex = [0u8; 4];
+        for slot in hex.iter_mut() {
+            *slot = self.next_byte()?.ok_or_else(|| {
+                io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated \\u escape")
+            })?;
+        }
+        let hex_str =
+            std::str::from_utf8(&hex).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))?;
+        u32::from_str_radix(hex_str, 16).map_err(|e| io::Error::new(io::ErrorKind::InvalidData, e))
+    }
+
+    fn read_number(&mut self) -> io::Result<String> {
+        let mut buf = String::new();
+        while let Some(b) = self.peek_byte()? {
+            if matches!(b, b'0'..=b'9' | b'-' | b'+' | b'.' | b'e' | b'E') {
+                buf.push(self.next_byte()?.unwrap() as char);
+            } else {
+                break;
+            }
+        }
+        if buf.is_empty() {
+            Err(io::Error::new(io::ErrorKind::InvalidData, "expected a number"))
+        } else {
+            Ok(buf)
+        }
+    }
+}
+
+/// Append `ch`'s UTF-8 encoding to `buf`.
+fn push_char(buf: &mut Vec<u8>, ch: char) {
+    let mut utf8_buf = [0u8; 4];
+    buf.extend_from_slice(ch.encode_utf8(&mut utf8_buf).as_bytes());
+}
+
+enum Container {
+    Object,
+    Array,
+}
+
+/// Tracks one open container while streaming tokens back out, so printing needs no lookahead
+/// beyond the current token.
+struct Frame {
+    kind: Container,
+    /// Number of complete entries already printed at this level, used to decide whether the next
+    /// entry needs a leading comma.
+    count: usize,
+    /// `Object`-only: whether the next token at this level is a key rather than a value.
+    expect_key: bool,
+    /// Set right after a key is printed, so its value is written inline instead of on a new line.
+    pending_key: bool,
+}
+
+fn write_indent<W: Write>(out: &mut W, depth: usize) -> io::Result<()> {
+    for _ in 0..depth {
+        write!(out, "  ")?;
+    }
+    Ok(())
 }
 
-fn pretty_json(filename: &str) -> Result<()> {
-    let input_file = File::open(&filename)?;
-    let reader = BufReader::new(input_file);
-    println!("Parsing {filename}");
-    let value: serde_json::Value = serde_json::from_reader(reader)?;
-    serde_json::to_writer_pretty(std::io::stdout(), &value)?;
+fn write_scalar<W: Write>(out: &mut W, tok: &Token) -> io::Result<()> {
+    match tok {
+        Token::String(s) => write!(out, "{}", serde_json::to_string(s).unwrap()),
+        Token::Number(n) => write!(out, "{n}"),
+        Token::Bool(b) => write!(out, "{b}"),
+        Token::Null => write!(out, "null"),
+        Token::ObjectStart | Token::ObjectEnd | Token::ArrayStart | Token::ArrayEnd => {
+            unreachable!("write_scalar called on a container token")
+        }
+    }
+}
+
+/// Write the comma (if this isn't the first entry at this level) and the newline+indent that
+/// precede a key or an array element. Values that directly follow a key are written inline
+/// instead, by the caller checking `pending_key` first.
+fn write_prefix<W: Write>(out: &mut W, stack: &[Frame]) -> io::Result<()> {
+    if let Some(frame) = stack.last() {
+        if frame.count > 0 {
+            write!(out, ",")?;
+        }
+        writeln!(out)?;
+        write_indent(out, stack.len())?;
+    }
     Ok(())
 }
+
+fn finish_value(stack: &mut [Frame]) {
+    if let Some(frame) = stack.last_mut() {
+        frame.count += 1;
+        if matches!(frame.kind, Container::Object) {
+            frame.expect_key = true;
+        }
+    }
+}
+
+/// Feed one token into the streaming pretty-printer. `stack` carries all the state needed to
+/// place commas, newlines and indentation correctly -- this never looks ahead.
+fn emit_pretty<W: Write>(out: &mut W, stack: &mut Vec<Frame>, tok: Token) -> io::Result<()> {
+    if matches!(tok, Token::ObjectEnd | Token::ArrayEnd) {
+        let frame = stack.pop().expect("unbalanced JSON input");
+        if frame.count > 0 {
+            writeln!(out)?;
+            write_indent(out, stack.len())?;
+        }
+        write!(out, "{}", if matches!(frame.kind, Container::Object) { "}" } else { "]" })?;
+        finish_value(stack);
+        return Ok(());
+    }
+
+    let is_key = matches!(stack.last(), Some(f) if matches!(f.kind, Container::Object) && f.expect_key);
+    if is_key {
+        let key = match &tok {
+            Token::String(s) => s.clone(),
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "object key must be a string")),
+        };
+        write_prefix(out, stack)?;
+        write!(out, "{}: ", serde_json::to_string(&key).unwrap())?;
+        if let Some(frame) = stack.last_mut() {
+            frame.expect_key = false;
+            frame.pending_key = true;
+        }
+        return Ok(());
+    }
+
+    let pending_key = matches!(stack.last(), Some(f) if f.pending_key);
+    if !pending_key {
+        write_prefix(out, stack)?;
+    }
+    if let Some(frame) = stack.last_mut() {
+        frame.pending_key = false;
+    }
+
+    match tok {
+        Token::ObjectStart => {
+            write!(out, "{{")?;
+            stack.push(Frame { kind: Container::Object, count: 0, expect_key: true, pending_key: false });
+        }
+        Token::ArrayStart => {
+            write!(out, "[")?;
+            stack.push(Frame { kind: Container::Array, count: 0, expect_key: false, pending_key: false });
+        }
+        scalar => {
+            write_scalar(out, &scalar)?;
+            finish_value(stack);
+        }
+    }
+    Ok(())
+}
+
+fn print_pretty<R: Read, W: Write>(lexer: &mut Lexer<R>, out: &mut W) -> io::Result<()> {
+    let mut stack: Vec<Frame> = Vec::new();
+    while let Some(tok) = lexer.next_token()? {
+        emit_pretty(out, &mut stack, tok)?;
+    }
+    writeln!(out)
+}
+
+/// Skip past the next whole value (scalar or container) without printing or buffering it, beyond
+/// the bracket-depth counter needed to find where it ends.
+fn skip_value<R: Read>(lexer: &mut Lexer<R>) -> io::Result<()> {
+    let tok = lexer
+        .next_token()?
+        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated value"))?;
+    if matches!(tok, Token::ObjectStart | Token::ArrayStart) {
+        let mut depth = 1usize;
+        while depth > 0 {
+            match lexer
+                .next_token()?
+                .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated value"))?
+            {
+                Token::ObjectStart | Token::ArrayStart => depth += 1,
+                Token::ObjectEnd | Token::ArrayEnd => depth -= 1,
+                _ => {}
+            }
+        }
+    }
+    Ok(())
+}
+
+/// Emit only the value of the named top-level key, skipping every other key's value without
+/// buffering it.
+fn print_filter<R: Read, W: Write>(lexer: &mut Lexer<R>, out: &mut W, key_name: &str) -> io::Result<()> {
+    match lexer.next_token()? {
+        Some(Token::ObjectStart) => {}
+        _ => {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                "--filter requires a top-level JSON object",
+            ))
+        }
+    }
+
+    let mut found = false;
+    loop {
+        match lexer
+            .next_token()?
+            .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated object"))?
+        {
+            Token::ObjectEnd => break,
+            Token::String(key) if key == key_name => {
+                found = true;
+                let mut stack: Vec<Frame> = Vec::new();
+                let first = lexer
+                    .next_token()?
+                    .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "missing value"))?;
+                emit_pretty(out, &mut stack, first)?;
+                while !stack.is_empty() {
+                    let tok = lexer
+                        .next_token()?
+                        .ok_or_else(|| io::Error::new(io::ErrorKind::UnexpectedEof, "unterminated value"))?;
+                    emit_pretty(out, &mut stack, tok)?;
+                }
+                writeln!(out)?;
+            }
+            Token::String(_) => skip_value(lexer)?,
+            _ => return Err(io::Error::new(io::ErrorKind::InvalidData, "expected an object key")),
+        }
+    }
+
+    if !found {
+        eprintln!("Warning: key `{key_name}` not found at the top level");
+    }
+    Ok(())
+}
+
+// ---- Terse mode, built on `serde_json::Value` --------------------------------------------------
+
+fn is_scalar(value: &serde_json::Value) -> bool {
+    !matches!(value, serde_json::Value::Array(_) | serde_json::Value::Object(_))
+}
+
+/// Whether `value` is an array/object whose direct children are all scalars, i.e. it's a
+/// candidate for being collapsed onto a single line.
+fn is_scalar_only_container(value: &serde_json::Value) -> bool {
+    match value {
+        serde_json::Value::Array(items) => items.iter().all(is_scalar),
+        serde_json::Value::Object(map) => map.values().all(is_scalar),
+        _ => false,
+    }
+}
+
+fn write_terse<W: Write>(out: &mut W, value: &serde_json::Value, depth: usize) -> io::Result<()> {
+    match value {
+        serde_json::Value::Array(items) if is_scalar_only_container(value) => {
+            write!(out, "[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(out, "{item}")?;
+            }
+            write!(out, "]")
+        }
+        serde_json::Value::Object(map) if is_scalar_only_container(value) => {
+            write!(out, "{{")?;
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ", ")?;
+                }
+                write!(out, "{}: {v}", serde_json::to_string(k).unwrap())?;
+            }
+            write!(out, "}}")
+        }
+        serde_json::Value::Array(items) => {
+            write!(out, "[")?;
+            for (i, item) in items.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                writeln!(out)?;
+                write_indent(out, depth + 1)?;
+                write_terse(out, item, depth + 1)?;
+            }
+            if !items.is_empty() {
+                writeln!(out)?;
+                write_indent(out, depth)?;
+            }
+            write!(out, "]")
+        }
+        serde_json::Value::Object(map) => {
+            write!(out, "{{")?;
+            for (i, (k, v)) in map.iter().enumerate() {
+                if i > 0 {
+                    write!(out, ",")?;
+                }
+                writeln!(out)?;
+                write_indent(out, depth + 1)?;
+                write!(out, "{}: ", serde_json::to_string(k).unwrap())?;
+                write_terse(out, v, depth + 1)?;
+            }
+            if !map.is_empty() {
+                writeln!(out)?;
+                write_indent(out, depth)?;
+            }
+            write!(out, "}}")
+        }
+        scalar => write!(out, "{scalar}"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Lexer;
+    use std::io::Cursor;
+
+    fn read_string(json_string_literal: &str) -> String {
+        Lexer::new(Cursor::new(json_string_literal.as_bytes())).read_string().unwrap()
+    }
+
+    #[test]
+    fn ascii_escape() {
+        assert_eq!(read_string(r#""a\nb\t\"c\"""#), "a\nb\t\"c\"");
+    }
+
+    #[test]
+    fn surrogate_pair() {
+        // U+1F600 GRINNING FACE as JSON spells it: the UTF-16 surrogate pair D83D DE00, written as two `\u` escapes.
+        assert_eq!(read_string("\"\\uD83D\\uDE00\""), "\u{1F600}");
+    }
+
+    #[test]
+    fn invalid_escape() {
+        // A lone high surrogate with no following low surrogate decodes to a replacement
+        // character, and the ordinary character right after it is unaffected.
+        assert_eq!(read_string(r#""\uD800x""#), "\u{FFFD}x");
+    }
+}